@@ -1,28 +1,64 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use anyhow::{Context, Result};
-use clap::{Parser, ValueEnum};
-use ignore::Walk;
-use log::{LevelFilter, error, info};
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use log::{LevelFilter, error, info, warn};
 use regex::Regex;
 
 use crate::{
-    mapping::{BaselineConfig, PathMapping, get_mapping},
-    nixdoc::AutoNixdoc,
+    coverage::{CoverageConfig, CoverageReport, FileCoverage},
+    diagnostic::{self, Diagnostic, OutputFormat, Severity},
+    doclint::{DocLint, RuleLevel},
+    error::{Error, ErrorKind},
+    fragment::{DirectorySettings, FRAGMENT_FILE, Fragments},
+    git,
+    init::Survey,
+    junit, linkcheck,
+    manpage::ManPages,
+    mapping::{BaselineConfig, MappingConfig, PathAction, PathMapping, get_mapping, relative_to},
+    markdown::DocPage,
+    nixdoc::{AutoNixdoc, GeneratedDoc},
+    nixsource::NixSource,
+    report::{FileReport, FileStatus, RunReport},
+    search::{SEARCH_INDEX_FILE, SearchIndex},
+    sourcelink::{DEFAULT_URL_TEMPLATE, SourceLinks},
+    walk::{PathFilter, UnmatchedPatterns, WalkConfig},
+    xref::SymbolTable,
 };
 
 /// Externally supported mapping types that can be selected by end users.
 ///
 /// Once a mapping is added here, it's part of the public API and will have
 /// to be supported over time; take care!
-#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum, serde::Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
 pub enum MappingType {
     /// Automatic mapping
+    #[default]
     Auto,
 }
 
 /// How individual nixdoc generation failures should be handled.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Default, serde::Deserialize)]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    ValueEnum,
+    Default,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
 pub enum FailureBehavior {
     /// Any individual failure should result in the generation process aborting immediately.
     Abort,
@@ -61,6 +97,32 @@ impl std::str::FromStr for LogLevel {
     }
 }
 
+/// Names of the logging levels, as accepted on the CLI and in configuration files.
+const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+impl<'de> serde::Deserialize<'de> for LogLevel {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let level = String::deserialize(deserializer)?;
+        level
+            .parse()
+            .map_err(|_| serde::de::Error::unknown_variant(&level, LOG_LEVELS))
+    }
+}
+
+impl schemars::JsonSchema for LogLevel {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "LogLevel".into()
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "description": "The level of logging to enable",
+            "type": "string",
+            "enum": LOG_LEVELS,
+        })
+    }
+}
+
 impl std::str::FromStr for FailureBehavior {
     type Err = String;
 
@@ -84,24 +146,51 @@ impl std::str::FromStr for FailureBehavior {
 #[derive(Parser, Debug)]
 #[command(version, long_about)]
 pub struct Driver {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// The directory containing the Nix library
-    #[arg(short, long)]
-    input_dir: PathBuf,
+    ///
+    /// Several libraries can instead be documented in a single run by configuring them as
+    /// `[[sources]]`, which are ignored when this is given. Every option can also be set in the
+    /// configuration file, such as `input_dir`, which this takes precedence over.
+    #[arg(short, long, global = true)]
+    input_dir: Option<PathBuf>,
 
     /// The directory where generated documentation will be stored
-    #[arg(short, long)]
-    output_dir: PathBuf,
+    #[arg(short, long, global = true)]
+    output_dir: Option<PathBuf>,
 
     /// The path mapping strategy that should be used to generate documentation
-    #[arg(short, long, value_enum, default_value_t = MappingType::Auto)]
-    mapping: MappingType,
+    ///
+    /// [default: auto]
+    #[arg(short, long, value_enum, global = true)]
+    mapping: Option<MappingType>,
 
     /// The desired behavior upon encountering individual failures
-    #[arg(short = 'f', long, value_enum)]
+    #[arg(short = 'f', long, value_enum, global = true)]
     on_failure: Option<FailureBehavior>,
 
-    /// The configuration file that should be used to customize mapping-dependent functionality
-    #[arg(short, long)]
+    /// Override the failure behavior for a single kind of error
+    ///
+    /// Kinds: nixdoc-missing, nixdoc-failed, outside-input-dir, mapping-failed, io,
+    /// invalid-unicode, invalid-fragment. May be given multiple times. Takes precedence over
+    /// --on-failure and the `[failure_behaviors]` configuration.
+    #[arg(long, global = true, value_name = "KIND=BEHAVIOR", value_parser = parse_kind_behavior)]
+    on_failure_for: Vec<(ErrorKind, FailureBehavior)>,
+
+    /// Apply a profile configured as `[profile.NAME]` in the configuration file
+    ///
+    /// May be given multiple times to run once for each profile, in order. The name of each
+    /// profile is then inserted into the names of report files, e.g. `report.public.json`, and
+    /// the logging level is that of the first profile.
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Vec<String>,
+
+    /// The configuration file that should be used to customize mapping-dependent functionality.
+    /// When not given, autonixdoc.toml is searched for in the input directory and its ancestors
+    /// up to the repository root, then in the current directory.
+    #[arg(short, long, global = true)]
     config: Option<PathBuf>,
 
     /// The level of logging to enable
@@ -112,20 +201,245 @@ pub struct Driver {
     /// - error: error messages only
     ///
     /// [default: warn]
-    #[arg(short, long, verbatim_doc_comment)]
+    #[arg(short, long, verbatim_doc_comment, global = true)]
     logging_level: Option<LogLevel>,
 
     /// Prefix for generated identifiers in the documentation
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     prefix: Option<String>,
 
     /// Prefix for anchor links in the generated documentation
-    #[arg(short = 'a', long)]
+    #[arg(short = 'a', long, global = true)]
     anchor_prefix: Option<String>,
 
     /// Regular expression pattern for identifying files to process
-    #[arg(long)]
+    #[arg(long, global = true)]
     regex_pattern: Option<String>,
+
+    /// Directory where section 3 man pages should be generated
+    ///
+    /// One man page is written for each documented category.
+    #[arg(long, global = true)]
+    man_dir: Option<PathBuf>,
+
+    /// Additionally generate one man page for each documented function
+    #[arg(long, global = true)]
+    man_per_function: bool,
+
+    /// Write a search-index.json describing every documented function to OUTPUT_DIR
+    #[arg(long, global = true)]
+    search_index: bool,
+
+    /// Base URL of the source repository, enabling "Source" links for documented functions
+    #[arg(long, global = true)]
+    repo_url: Option<String>,
+
+    /// URL template for "Source" links
+    ///
+    /// Supports the {repo}, {rev}, {path} and {line} placeholders.
+    ///
+    /// [default: {repo}/blob/{rev}/{path}#L{line}]
+    #[arg(long, global = true, verbatim_doc_comment)]
+    source_url_template: Option<String>,
+
+    /// Revision that "Source" links should point at
+    ///
+    /// [default: the commit checked out in the repository containing INPUT_DIR]
+    #[arg(long, global = true, verbatim_doc_comment)]
+    source_rev: Option<String>,
+
+    /// Rewrite references to documented functions in code spans into links
+    ///
+    /// References that look like identifiers but can't be resolved are reported as warnings.
+    #[arg(long, global = true)]
+    cross_references: bool,
+
+    /// Don't print the summary of how many files were written, skipped and failed
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Write the status, destination, category, error and timing of every file to a JSON file
+    #[arg(long, global = true, value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Write the outcome of every processed source file as a JUnit XML test case
+    ///
+    /// Test cases are grouped into one suite per top-level directory of INPUT_DIR.
+    #[arg(long, global = true, value_name = "FILE")]
+    junit: Option<PathBuf>,
+
+    /// Abort once more than this many failures have been logged
+    #[arg(long, global = true, value_name = "COUNT")]
+    max_failures: Option<usize>,
+
+    /// Abort the run once more than this ratio (0 to 1) of the files to process failed
+    #[arg(long, global = true, value_name = "RATIO")]
+    max_failure_ratio: Option<f64>,
+
+    /// Fail unless at least this percentage of all exported attributes across every input
+    /// directory is documented
+    #[arg(long, global = true, value_name = "PERCENT")]
+    min_coverage: Option<f64>,
+
+    /// Fail unless at least PERCENT of the attributes in files matching GLOB are documented
+    ///
+    /// GLOB is matched against file paths relative to INPUT_DIR, where only `**` matches across
+    /// directories. May be given multiple times.
+    #[arg(long, global = true, value_name = "GLOB=PERCENT", value_parser = parse_glob_threshold)]
+    min_coverage_dir: Vec<(String, f64)>,
+
+    /// Fail if any exported attribute in files matching GLOB is undocumented
+    ///
+    /// GLOB is matched against file paths relative to INPUT_DIR, where only `**` matches across
+    /// directories. May be given multiple times.
+    #[arg(long, global = true, value_name = "GLOB")]
+    forbid_undocumented: Vec<String>,
+
+    /// Write generation failures, lint findings and coverage violations to a SARIF 2.1.0 file
+    #[arg(long, global = true, value_name = "FILE")]
+    sarif: Option<PathBuf>,
+
+    /// Print generation failures, lint findings and coverage violations to stderr as GitHub
+    /// Actions annotations
+    #[arg(long, global = true)]
+    github_annotations: bool,
+
+    /// Process files ignored by `.gitignore`, `.ignore` and git exclude files
+    #[arg(long, global = true)]
+    no_gitignore: bool,
+
+    /// Additionally respect gitignore-style files with this name, such as `.autonixdocignore`
+    ///
+    /// May be given multiple times.
+    #[arg(long, global = true, value_name = "NAME")]
+    ignore_file: Vec<String>,
+
+    /// Follow symbolic links when walking INPUT_DIR
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+
+    /// Only walk this many directory levels below INPUT_DIR
+    #[arg(long, global = true, value_name = "DEPTH")]
+    max_depth: Option<usize>,
+
+    /// Process hidden files and directories
+    #[arg(long, global = true)]
+    hidden: bool,
+}
+
+/// Parses a `GLOB=PERCENT` coverage requirement.
+fn parse_glob_threshold(value: &str) -> Result<(String, f64), String> {
+    let (glob, percent) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected GLOB=PERCENT, found `{}`", value))?;
+    let percent: f64 = percent
+        .parse()
+        .map_err(|_| format!("invalid percentage `{}`", percent))?;
+    Ok((glob.to_string(), percent))
+}
+
+/// Parses a `KIND=BEHAVIOR` failure behavior override.
+fn parse_kind_behavior(value: &str) -> Result<(ErrorKind, FailureBehavior), String> {
+    let (kind, behavior) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected KIND=BEHAVIOR, found `{}`", value))?;
+    Ok((ErrorKind::from_str(kind, true)?, behavior.parse()?))
+}
+
+/// Commands other than documentation generation, which is performed when no command is given.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check doc comments in INPUT_DIR and generated documentation in OUTPUT_DIR
+    ///
+    /// When INPUT_DIR is given, doc comments of every processed file are checked for common
+    /// authoring mistakes:
+    ///
+    ///   line-comment-doc   attribute documented with a `#` comment [default: warning]
+    ///   block-comment-doc  attribute documented with a `/* */` comment [default: warning]
+    ///   empty-doc          empty `/** */` doc comment [default: error]
+    ///   section-format     malformed section heading such as `## Type` [default: error]
+    ///
+    /// When OUTPUT_DIR is given, all markdown files within it are checked for duplicate anchors,
+    /// relative links to missing files and fragment links to missing anchors.
+    ///
+    /// Exits with a non-zero status if any errors are found.
+    #[command(verbatim_doc_comment)]
+    Lint(LintArgs),
+    /// Report how many attributes exported by files in INPUT_DIR are documented
+    ///
+    /// Coverage is reported per file, per directory including its subdirectories and in total,
+    /// along with the names of all undocumented attributes. Attributes introduced with `inherit`
+    /// are not counted.
+    Coverage(CoverageArgs),
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Write a commented configuration file suited to INPUT_DIR
+    ///
+    /// INPUT_DIR is inspected for its number of Nix files, a `default.nix` entry point and
+    /// directories such as `tests` that usually shouldn't be documented. An existing `docs`
+    /// directory or mdBook `book.toml` next to the configuration file determines the suggested
+    /// output directory.
+    ///
+    /// The file is written to the path given with --config, or `autonixdoc.toml`.
+    Init(InitArgs),
+}
+
+#[derive(Args, Debug)]
+struct InitArgs {
+    /// Overwrite an existing configuration file
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Show the effective settings for a source file
+    ///
+    /// Settings from the CLI, environment and configuration file are combined with those of all
+    /// `autonixdoc.toml` fragments in the directories between the input directory and the file.
+    Show {
+        /// The source file, which must be within an input directory
+        path: PathBuf,
+    },
+    /// Check the configuration file and the fragments within every input directory
+    ///
+    /// Unknown keys and invalid values are reported with their location, along with invalid
+    /// patterns, thresholds and lint rules. Exits with a non-zero status if any problems are found.
+    Validate,
+    /// Print a JSON Schema for the configuration file of the selected mapping
+    ///
+    /// Editors can use the schema to complete and validate `autonixdoc.toml`, for example with a
+    /// `#:schema ./autonixdoc.schema.json` directive at the top of the file.
+    Schema,
+}
+
+#[derive(Args, Debug)]
+struct LintArgs {
+    /// The format in which findings are reported
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Override the level of a doc-comment rule (off, warning or error)
+    ///
+    /// May be given multiple times. Takes precedence over the `[lint.severity]` configuration.
+    #[arg(long, value_name = "RULE=LEVEL", value_parser = parse_rule_level)]
+    severity: Vec<(String, RuleLevel)>,
+}
+
+/// Parses a `RULE=LEVEL` lint rule override.
+fn parse_rule_level(value: &str) -> Result<(String, RuleLevel), String> {
+    let (rule, level) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected RULE=LEVEL, found `{}`", value))?;
+    Ok((rule.to_string(), level.parse()?))
+}
+
+#[derive(Args, Debug)]
+struct CoverageArgs {
+    /// The format in which coverage is reported
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
 }
 
 // TODO: Implement another mapper to demonstrate how it works
@@ -135,6 +449,87 @@ fn resolve_option<T: std::str::FromStr>(cli_value: Option<T>, env_key: &str) ->
     cli_value.or_else(|| std::env::var(env_key).ok().and_then(|s| s.parse().ok()))
 }
 
+/// Resolves a boolean flag that is enabled if set on the CLI or set to "true" in the environment.
+fn resolve_flag(cli_value: bool, env_key: &str) -> bool {
+    cli_value || resolve_option(None, env_key).unwrap_or(false)
+}
+
+/// Resolves a boolean flag that is enabled if set on the CLI, or else if enabled in the
+/// environment or, failing that, the configuration file.
+fn resolve_flag_with_config(cli_value: bool, env_key: &str, config_value: Option<bool>) -> bool {
+    cli_value || resolve_with_config(None, env_key, config_value).unwrap_or(false)
+}
+
+/// Formats an error followed by the chain of errors that caused it, separated by colons.
+fn error_chain(error: &(dyn std::error::Error + 'static)) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+/// Describes a file for which documentation couldn't be generated.
+fn generation_failure(path: &Path, error: &dyn std::fmt::Display) -> Diagnostic {
+    Diagnostic {
+        rule: "generation-failed".to_string(),
+        severity: Severity::Error,
+        path: path.to_path_buf(),
+        line: None,
+        message: format!("{:#}", error).trim_end().to_string(),
+    }
+}
+
+/// Creates the filter selecting the paths walked within an input directory.
+///
+/// Paths excluded by `fragments` within the input directory are left out as well.
+fn path_filter(
+    config: &impl BaselineConfig,
+    behaviors: &Behaviors,
+    input_dir: &Path,
+    fragments: &Arc<Fragments>,
+) -> Result<Arc<PathFilter>> {
+    Ok(Arc::new(
+        PathFilter::new(
+            input_dir,
+            &config.include(),
+            &config.exclude(),
+            behaviors.walk.clone(),
+        )?
+        .with_fragments(Arc::clone(fragments)),
+    ))
+}
+
+/// Wraps a failure to load the configuration fragments applying to `path`, so that it's handled
+/// like any other failure to process the file.
+fn fragment_error(path: &Path, error: &anyhow::Error) -> Error {
+    Error::InvalidFragment {
+        path: path.to_path_buf(),
+        reason: format!("{:#}", error),
+    }
+}
+
+/// Joins a prefix and a nixdoc category into a fully-qualified category name.
+fn qualified_category(prefix: &str, category: &str) -> String {
+    if prefix.is_empty() {
+        category.to_string()
+    } else {
+        format!("{}.{}", prefix, category)
+    }
+}
+
+/// Inserts a profile name into a file name before its extension, e.g. `report.public.json`.
+fn profile_path(path: &Path, profile: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, profile, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, profile),
+    };
+    path.with_file_name(name)
+}
+
 /// Resolves configuration values with three-tier priority: CLI > environment > config file.
 ///
 /// This function implements the priority system where CLI arguments have the highest priority,
@@ -156,20 +551,15 @@ fn resolve_with_config<T: std::str::FromStr + Clone>(
 }
 
 /// Strategy for identifying which files should be processed for documentation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum PathIdentification {
     /// Files ending in ".nix"
+    #[default]
     NixExtension,
     /// Files matching a user-provided regular expression
     Regex(Regex),
 }
 
-impl Default for PathIdentification {
-    fn default() -> Self {
-        Self::NixExtension
-    }
-}
-
 impl PathIdentification {
     /// Creates a PathIdentification strategy from an optional regex pattern.
     ///
@@ -200,208 +590,1565 @@ impl PathIdentification {
 }
 
 mod env_vars {
-    pub const CONFIG: &'static str = "AUTONIXDOC_CONFIG";
-    pub const ON_FAILURE: &'static str = "AUTONIXDOC_ON_FAILURE";
-    pub const PREFIX: &'static str = "AUTONIXDOC_PREFIX";
-    pub const ANCHOR_PREFIX: &'static str = "AUTONIXDOC_ANCHOR_PREFIX";
-    pub const LOGGING_LEVEL: &'static str = "AUTONIXDOC_LOGGING_LEVEL";
-    pub const REGEX_PATTERN: &'static str = "AUTONIXDOC_REGEX_PATTERN";
+    pub const CONFIG: &str = "AUTONIXDOC_CONFIG";
+    pub const PROFILE: &str = "AUTONIXDOC_PROFILE";
+    pub const ON_FAILURE: &str = "AUTONIXDOC_ON_FAILURE";
+    pub const PREFIX: &str = "AUTONIXDOC_PREFIX";
+    pub const ANCHOR_PREFIX: &str = "AUTONIXDOC_ANCHOR_PREFIX";
+    pub const LOGGING_LEVEL: &str = "AUTONIXDOC_LOGGING_LEVEL";
+    pub const REGEX_PATTERN: &str = "AUTONIXDOC_REGEX_PATTERN";
+    pub const MAN_DIR: &str = "AUTONIXDOC_MAN_DIR";
+    pub const MAN_PER_FUNCTION: &str = "AUTONIXDOC_MAN_PER_FUNCTION";
+    pub const SEARCH_INDEX: &str = "AUTONIXDOC_SEARCH_INDEX";
+    pub const REPO_URL: &str = "AUTONIXDOC_REPO_URL";
+    pub const SOURCE_URL_TEMPLATE: &str = "AUTONIXDOC_SOURCE_URL_TEMPLATE";
+    pub const SOURCE_REV: &str = "AUTONIXDOC_SOURCE_REV";
+    pub const CROSS_REFERENCES: &str = "AUTONIXDOC_CROSS_REFERENCES";
+    pub const MIN_COVERAGE: &str = "AUTONIXDOC_MIN_COVERAGE";
+    pub const QUIET: &str = "AUTONIXDOC_QUIET";
+    pub const REPORT: &str = "AUTONIXDOC_REPORT";
+    pub const JUNIT: &str = "AUTONIXDOC_JUNIT";
+    pub const MAX_FAILURES: &str = "AUTONIXDOC_MAX_FAILURES";
+    pub const MAX_FAILURE_RATIO: &str = "AUTONIXDOC_MAX_FAILURE_RATIO";
+    pub const SARIF: &str = "AUTONIXDOC_SARIF";
+    pub const GITHUB_ANNOTATIONS: &str = "AUTONIXDOC_GITHUB_ANNOTATIONS";
+    pub const NO_GITIGNORE: &str = "AUTONIXDOC_NO_GITIGNORE";
+    pub const FOLLOW_SYMLINKS: &str = "AUTONIXDOC_FOLLOW_SYMLINKS";
+    pub const MAX_DEPTH: &str = "AUTONIXDOC_MAX_DEPTH";
+    pub const HIDDEN: &str = "AUTONIXDOC_HIDDEN";
+}
+
+/// An input root documented during generation, resolved from the CLI or configuration.
+struct Source {
+    /// Path identifying the source in reports, empty for a single input directory
+    name: PathBuf,
+    input_dir: PathBuf,
+    output_dir: PathBuf,
+    category_root: Option<String>,
+    prefix: String,
+    anchor_prefix: String,
 }
 
 struct Behaviors {
     on_failure: FailureBehavior,
+    /// Failure behaviors overriding `on_failure` for specific kinds of error
+    on_failure_by_kind: BTreeMap<ErrorKind, FailureBehavior>,
     path_identification: PathIdentification,
+    /// Number of logged failures after which the run is aborted
+    max_failures: Option<usize>,
+    /// Ratio of failed to processed files above which a run with logged failures is fatal
+    max_failure_ratio: Option<f64>,
+    /// How input directories are walked
+    walk: WalkConfig,
 }
 
 impl Behaviors {
     fn new(on_failure: Option<FailureBehavior>, regex_pattern: Option<String>) -> Result<Self> {
         Ok(Self {
             on_failure: on_failure.unwrap_or_default(),
+            on_failure_by_kind: BTreeMap::new(),
             path_identification: PathIdentification::from_pattern(regex_pattern)?,
+            max_failures: None,
+            max_failure_ratio: None,
+            walk: WalkConfig::default(),
         })
     }
-}
-
-mod constants {
-    pub const DEFAULT_CONFIG_PATH: &'static str = "autonixdoc.toml";
-}
-
-impl Driver {
-    pub fn run(self) -> Result<()> {
-        let mapping = get_mapping(self.mapping, &self.input_dir, &self.output_dir);
-        let config = Self::resolve_config(
-            &mapping,
-            resolve_option(self.config.clone(), env_vars::CONFIG),
-        )
-        .with_context(|| "Failed to resolve configuration file")?;
-
-        let failure_behavior = resolve_with_config(
-            self.on_failure,
-            env_vars::ON_FAILURE,
-            config.failure_behavior(),
-        );
-
-        let regex_pattern = resolve_option(self.regex_pattern.clone(), env_vars::REGEX_PATTERN);
-        let behaviors = Behaviors::new(failure_behavior, regex_pattern)?;
-
-        let logging_level = resolve_with_config(
-            self.logging_level,
-            env_vars::LOGGING_LEVEL,
-            config.logging_level(),
-        );
-        self.initialize_logging(logging_level);
 
-        let prefix = resolve_with_config(self.prefix.clone(), env_vars::PREFIX, config.prefix())
-            .unwrap_or_default();
-
-        let anchor_prefix = resolve_with_config(
-            self.anchor_prefix.clone(),
-            env_vars::ANCHOR_PREFIX,
-            config.anchor_prefix(),
-        )
-        .unwrap_or_default();
-
-        let autonixdoc = AutoNixdoc::new(&prefix, &anchor_prefix, self.input_dir.clone(), mapping);
-        self.run_in_path(&autonixdoc, &config, &behaviors, &self.input_dir)
+    /// How failures caused by an error of the given kind should be handled.
+    ///
+    /// Behaviors configured for the kind of error take precedence over `subtree`, the behavior
+    /// configured by fragments for the failing file, which takes precedence over `on_failure`.
+    fn on_failure_for(&self, kind: ErrorKind, subtree: Option<FailureBehavior>) -> FailureBehavior {
+        self.on_failure_by_kind
+            .get(&kind)
+            .copied()
+            .or(subtree)
+            .unwrap_or(self.on_failure)
     }
 
-    fn initialize_logging(&self, logging_level: Option<LogLevel>) {
-        if let Some(level) = logging_level {
-            env_logger::builder().filter_level(level.into()).init();
-        } else {
-            env_logger::init();
+    /// Checks that the behaviors are consistent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `max_failure_ratio` isn't between 0 and 1.
+    fn validate(&self) -> Result<()> {
+        if let Some(ratio) = self.max_failure_ratio
+            && !(0.0..=1.0).contains(&ratio)
+        {
+            bail!("Maximum failure ratio {} is not between 0 and 1", ratio);
         }
+        Ok(())
     }
 
-    fn resolve_config<M: PathMapping>(_mapping: &M, path: Option<PathBuf>) -> Result<M::Config> {
-        let default_config = PathBuf::from(constants::DEFAULT_CONFIG_PATH);
-
-        if let Some(path) = path {
-            info!(
-                "Attempting to load user-provided configuration at {}",
-                path.display()
+    /// Escalates logged failures to aborting the run once they exceed `max_failures`, or
+    /// `max_failure_ratio` of the files selected for the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `failures` - Number of failures logged so far
+    /// * `selected` - Number of files selected for the whole run, see [`Listing::selected`]
+    fn check_failures(&self, failures: usize, selected: usize) -> Result<()> {
+        if let Some(max_failures) = self.max_failures
+            && failures > max_failures
+        {
+            bail!(
+                "Aborting after {} failures, exceeding the maximum of {}",
+                failures,
+                max_failures
             );
-
-            let config = std::fs::read_to_string(&path)
-                .with_context(|| "Failed to read configuration file from user-provided path")?;
-            Ok(toml::from_str(&config).with_context(
-                || "Failed to parse user-provided configuration file as valid TOML",
-            )?)
-        } else if let Ok(exists) = std::fs::exists(&default_config)
-            && exists
+        }
+        // Fewer files can't be processed than were selected, so the ratio can only grow from here
+        if let Some(max_ratio) = self.max_failure_ratio
+            && failures as f64 > max_ratio * selected as f64
         {
-            info!(
-                "Attempting to load default configuration at {}",
-                default_config.display()
+            bail!(
+                "Aborting after {} of {} files failed, exceeding the maximum failure ratio of {}",
+                failures,
+                selected,
+                max_ratio
             );
+        }
+        Ok(())
+    }
 
-            let config = std::fs::read_to_string(&default_config).with_context(
-                || "A configuration file exists at the default path, but cannot be read",
-            )?;
-            Ok(toml::from_str(&config)
-                .with_context(|| "Failed to parse default configuration file as valid TOML")?)
-        } else {
-            info!("No configuration found, falling back to defaults");
-            Ok(Default::default())
+    /// Determines how a run ended given the outcome of every file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if logged failures exceed `max_failure_ratio` of processed files.
+    fn outcome(&self, report: &RunReport) -> Result<Outcome> {
+        let logged = report.logged_failures();
+        if logged == 0 {
+            return Ok(Outcome::Clean);
         }
+
+        let processed = report.summary().processed;
+        let ratio = logged as f64 / processed as f64;
+        if let Some(max_ratio) = self.max_failure_ratio
+            && ratio > max_ratio
+        {
+            bail!(
+                "{} of {} files failed, exceeding the maximum failure ratio of {}",
+                logged,
+                processed,
+                max_ratio
+            );
+        }
+
+        Ok(Outcome::CompletedWithFailures)
     }
+}
 
-    fn run_in_path<'a, M: PathMapping>(
-        &self,
-        autonixdoc: &AutoNixdoc<'a, M>,
-        config: &M::Config,
+/// The paths within an input directory, listed before any documentation is generated so that
+/// the number of files to process is known up front.
+struct Listing {
+    root: PathBuf,
+    fragments: Arc<Fragments>,
+    entries: Vec<ListedPath>,
+}
+
+enum ListedPath {
+    /// Listing a directory failed
+    Error(ignore::Error),
+    /// A file selected for processing
+    Selected(PathBuf),
+    /// A file left out by path identification
+    Unselected(PathBuf),
+}
+
+impl Listing {
+    /// Lists the files within `root`, recording which include and exclude patterns matched.
+    fn new(
+        config: &impl BaselineConfig,
         behaviors: &Behaviors,
-        path: &Path,
-    ) -> Result<()> {
-        for entry in Walk::new(path) {
+        root: &Path,
+        unmatched: &mut UnmatchedPatterns,
+    ) -> Result<Self> {
+        let fragments = Arc::new(Fragments::new(root));
+        let filter = path_filter(config, behaviors, root, &fragments)?;
+        let mut entries = Vec::new();
+        for entry in filter.walk() {
             let path = match entry {
                 Ok(entry) => entry.into_path(),
-                Err(e) => match behaviors.on_failure {
-                    FailureBehavior::Abort => {
-                        return Err(e).with_context(|| "Failed to list directory");
-                    }
-                    FailureBehavior::Log => {
-                        error!("Failed to list directory: {}", e);
-                        continue;
-                    }
-                    FailureBehavior::Skip => continue,
-                },
+                Err(e) => {
+                    entries.push(ListedPath::Error(e));
+                    continue;
+                }
             };
+            if path.is_dir() {
+                continue;
+            }
 
-            if !path.is_dir() && behaviors.path_identification.should_process(&path) {
-                info!("Generating documentation for {}", path.display());
-                let exec_result = autonixdoc.execute(config, &path);
-                if let Err(e) = exec_result {
-                    match behaviors.on_failure {
-                        FailureBehavior::Abort => {
-                            return Err(e).with_context(|| {
-                                format!(
-                                    "Documentation generation failed for file {}",
-                                    path.display()
-                                )
-                            });
-                        }
-                        FailureBehavior::Log => {
-                            error!(
-                                "Failed to generate documentation for {}: {}",
-                                path.display(),
-                                e
-                            );
-                            continue;
-                        }
-                        FailureBehavior::Skip => continue,
-                    }
-                }
+            if behaviors.path_identification.should_process(&path) && filter.is_included(&path) {
+                entries.push(ListedPath::Selected(path));
             } else {
-                info!("Skipping uninteresting path {}", path.display());
+                entries.push(ListedPath::Unselected(path));
             }
         }
+        unmatched.retain(&filter);
 
-        Ok(())
+        Ok(Listing {
+            root: root.to_path_buf(),
+            fragments,
+            entries,
+        })
+    }
+
+    /// Number of entries counted as processed files once the run completes, including
+    /// directories that couldn't be listed.
+    fn selected(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| !matches!(entry, ListedPath::Unselected(_)))
+            .count()
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::PathBuf;
+/// How a run that didn't fail outright ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Everything succeeded, or failures were configured to be skipped
+    Clean,
+    /// The run completed, but failures were logged along the way
+    CompletedWithFailures,
+}
 
-    #[test]
-    fn test_path_identification_extension_default() {
-        let identification = PathIdentification::default();
-        assert!(matches!(identification, PathIdentification::NixExtension));
+impl Outcome {
+    /// Exit code of the process: 0 when clean and 2 when failures were logged.
+    ///
+    /// Fatal errors exit with 1.
+    pub fn exit_code(self) -> ExitCode {
+        match self {
+            Self::Clean => ExitCode::SUCCESS,
+            Self::CompletedWithFailures => ExitCode::from(2),
+        }
     }
+}
 
-    #[test]
-    fn test_path_identification_from_pattern_none() {
-        let identification = PathIdentification::from_pattern(None).unwrap();
-        assert!(matches!(identification, PathIdentification::NixExtension));
-    }
+mod constants {
+    pub const DEFAULT_CONFIG_PATH: &str = "autonixdoc.toml";
+}
 
-    #[test]
-    fn test_path_identification_from_pattern_some() {
-        let identification = PathIdentification::from_pattern(Some(r"\.rs$".to_string())).unwrap();
-        assert!(matches!(identification, PathIdentification::Regex(_)));
-    }
+impl Driver {
+    pub fn run(self) -> Result<Outcome> {
+        // Neither command depends on an existing configuration file, which may not be valid yet
+        match &self.command {
+            Some(Command::Init(args)) => return self.init(args).map(|_| Outcome::Clean),
+            Some(Command::Config(ConfigCommand::Schema)) => {
+                let cwd = Path::new(".");
+                let schema = crate::config::schema(&get_mapping(
+                    self.mapping.unwrap_or_default(),
+                    cwd,
+                    cwd,
+                ))?;
+                println!("{}", schema);
+                return Ok(Outcome::Clean);
+            }
+            _ => {}
+        }
 
-    #[test]
-    fn test_path_identification_from_pattern_invalid_regex() {
-        let result = PathIdentification::from_pattern(Some("[".to_string()));
-        assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("Invalid regex pattern")
-        );
-    }
+        let profiles = self.profiles();
+        let profiles: Vec<Option<&str>> = if profiles.is_empty() {
+            vec![None]
+        } else {
+            profiles
+                .iter()
+                .map(|profile| Some(profile.as_str()))
+                .collect()
+        };
 
-    #[test]
-    fn test_path_identification_extension_should_process_nix() {
-        let identification = PathIdentification::NixExtension;
-        let path = PathBuf::from("/path/to/file.nix");
-        assert!(identification.should_process(&path));
-    }
+        let configs = profiles
+            .into_iter()
+            .map(|profile| self.load_config(profile))
+            .collect::<Result<Vec<_>>>()?;
+        self.initialize_logging_for(&configs);
+
+        let mut outcome = Outcome::Clean;
+        for config in configs {
+            let mut diagnostics = Vec::new();
+            let result = match &self.command {
+                Some(Command::Lint(args)) => self
+                    .lint(args, &config, &mut diagnostics)
+                    .map(|_| Outcome::Clean),
+                Some(Command::Coverage(args)) => self
+                    .coverage(args, &config, &mut diagnostics)
+                    .map(|_| Outcome::Clean),
+                Some(Command::Config(ConfigCommand::Show { path })) => {
+                    self.show_config(path, &config).map(|_| Outcome::Clean)
+                }
+                Some(Command::Config(ConfigCommand::Validate)) => {
+                    self.validate_config(&config).map(|_| Outcome::Clean)
+                }
+                _ => self.generate(&config, &mut diagnostics),
+            };
+
+            // Problems are reported even when the run fails, as that's when they matter most
+            self.report_diagnostics(&config, &diagnostics)?;
+            if result? == Outcome::CompletedWithFailures {
+                outcome = Outcome::CompletedWithFailures;
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Resolves the path of a file reporting on the run.
+    ///
+    /// When several profiles are run, the name of the active profile is inserted into the file
+    /// name so that profiles don't overwrite each other's reports.
+    ///
+    /// # Arguments
+    ///
+    /// * `cli_value` - Path from CLI arguments
+    /// * `env_key` - Environment variable key to check
+    /// * `config` - The configuration of the active profile
+    /// * `config_value` - Path from the configuration file
+    fn report_path(
+        &self,
+        cli_value: Option<PathBuf>,
+        env_key: &str,
+        config: &impl BaselineConfig,
+        config_value: Option<PathBuf>,
+    ) -> Option<PathBuf> {
+        let path = resolve_with_config(cli_value, env_key, config_value)?;
+        match config.active_profile() {
+            Some(profile) if self.profiles().len() > 1 => Some(profile_path(&path, &profile)),
+            _ => Some(path),
+        }
+    }
+
+    /// Returns the profiles selected on the CLI or, as a comma-separated list, in the
+    /// environment.
+    fn profiles(&self) -> Vec<String> {
+        if !self.profile.is_empty() {
+            return self.profile.clone();
+        }
+        std::env::var(env_vars::PROFILE)
+            .map(|profiles| {
+                profiles
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|profile| !profile.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Emits diagnostics collected during the run in the requested machine-readable formats.
+    fn report_diagnostics(
+        &self,
+        config: &impl BaselineConfig,
+        diagnostics: &[Diagnostic],
+    ) -> Result<()> {
+        if let Some(path) =
+            self.report_path(self.sarif.clone(), env_vars::SARIF, config, config.sarif())
+        {
+            std::fs::write(&path, diagnostic::sarif(diagnostics))
+                .with_context(|| format!("Failed to write SARIF log: {}", path.display()))?;
+        }
+
+        if resolve_flag_with_config(
+            self.github_annotations,
+            env_vars::GITHUB_ANNOTATIONS,
+            config.github_annotations(),
+        ) {
+            // Annotations go to stderr, which the runner also reads, to keep stdout parseable
+            eprint!("{}", diagnostic::github_annotations(diagnostics));
+        }
+
+        Ok(())
+    }
+
+    /// Loads the configuration for this run, applying `profile` if given.
+    ///
+    /// All mappings share a single configuration type, so the configuration is loaded before the
+    /// mapping is known; it may select the mapping itself.
+    fn load_config(&self, profile: Option<&str>) -> Result<MappingConfig> {
+        let cwd = Path::new(".");
+        Self::resolve_config(
+            &get_mapping(self.mapping.unwrap_or_default(), cwd, cwd),
+            resolve_option(self.config.clone(), env_vars::CONFIG),
+            self.config_search_dir(),
+            profile,
+        )
+        .with_context(|| "Failed to resolve configuration file")
+    }
+
+    /// Returns the path mapping strategy given on the CLI or in the configuration.
+    fn mapping(&self, config: &impl BaselineConfig) -> MappingType {
+        self.mapping.or(config.mapping()).unwrap_or_default()
+    }
+
+    /// Returns the directory that configuration discovery starts from: the input directory if
+    /// one is given, otherwise the current directory.
+    fn config_search_dir(&self) -> &Path {
+        self.input_dir.as_deref().unwrap_or(Path::new("."))
+    }
+
+    fn output_dir(&self, config: &impl BaselineConfig) -> Result<PathBuf> {
+        self.output_dir
+            .clone()
+            .or_else(|| config.output_dir())
+            .with_context(|| {
+                "An output directory must be provided with --output-dir or `output_dir` in the configuration"
+            })
+    }
+
+    fn generate(
+        &self,
+        config: &MappingConfig,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<Outcome> {
+        let output_dir = self.output_dir(config)?;
+        let mapping_type = self.mapping(config);
+        let behaviors = self.resolve_behaviors(config)?;
+        let requirements = self.coverage_requirements(config)?;
+        let sources = self.sources(config, &output_dir)?;
+
+        // Sources may lie in different repositories, so each links to its own
+        let source_links = sources
+            .iter()
+            .map(|source| self.source_links(config, &source.input_dir))
+            .collect::<Result<Vec<_>>>()?;
+
+        let started = Instant::now();
+        let mut report = RunReport::default();
+        let mut generated = Vec::new();
+        let mut result = Ok(());
+        // Ignored paths and patterns apply to every source, so they are only reported once none
+        // of the sources is affected by them
+        let mut ineffective: Option<BTreeSet<PathBuf>> = None;
+        let mut unmatched = UnmatchedPatterns::default();
+        // Every source is listed up front, so that the run can abort as soon as too large a
+        // share of all its files failed
+        let listings = sources
+            .iter()
+            .map(|source| Listing::new(config, &behaviors, &source.input_dir, &mut unmatched))
+            .collect::<Result<Vec<_>>>()?;
+        let selected = listings.iter().map(Listing::selected).sum();
+        for ((source, links), listing) in sources.iter().zip(&source_links).zip(listings) {
+            let mapping = get_mapping(mapping_type, &source.input_dir, &source.output_dir);
+            let paths = mapping.ineffective_ignore_paths(config);
+            ineffective = Some(match ineffective {
+                Some(previous) => previous.intersection(&paths).cloned().collect(),
+                None => paths,
+            });
+            let autonixdoc = AutoNixdoc::new(
+                &source.prefix,
+                &source.anchor_prefix,
+                source.input_dir.clone(),
+                mapping,
+            )
+            .with_category_root(source.category_root.clone());
+
+            match Self::run_in_path(
+                &autonixdoc,
+                config,
+                &behaviors,
+                listing,
+                selected,
+                diagnostics,
+                &mut report,
+            ) {
+                Ok(docs) => generated.extend(docs.into_iter().map(|doc| (doc, links.as_ref()))),
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        report.finish(started.elapsed());
+        for entry in ineffective.iter().flatten() {
+            warn!(
+                "Ignored path {} doesn't exist relative to any input directory",
+                entry.display()
+            );
+        }
+        unmatched.warn();
+
+        if !resolve_flag_with_config(self.quiet, env_vars::QUIET, config.quiet()) {
+            match config.active_profile() {
+                Some(profile) => eprintln!("[{}] {}", profile, report.summary()),
+                None => eprintln!("{}", report.summary()),
+            }
+        }
+        if let Some(path) = self.report_path(
+            self.report.clone(),
+            env_vars::REPORT,
+            config,
+            config.report(),
+        ) {
+            report.write(&path)?;
+        }
+        if let Some(path) =
+            self.report_path(self.junit.clone(), env_vars::JUNIT, config, config.junit())
+        {
+            let sources: Vec<(&Path, &Path)> = sources
+                .iter()
+                .map(|s| (s.name.as_path(), s.input_dir.as_path()))
+                .collect();
+            std::fs::write(&path, junit::junit(&report, &sources))
+                .with_context(|| format!("Failed to write JUnit report: {}", path.display()))?;
+        }
+
+        result?;
+        let outcome = behaviors.outcome(&report)?;
+        self.post_process(config, &output_dir, &generated)?;
+
+        if !requirements.is_empty() {
+            let mut reports = Vec::new();
+            for source in &sources {
+                let mapping = get_mapping(mapping_type, &source.input_dir, &source.output_dir);
+                // Unmatched patterns were already reported during generation
+                reports.push(self.analyze_coverage(
+                    &mapping,
+                    config,
+                    &behaviors,
+                    &source.input_dir,
+                    &mut UnmatchedPatterns::default(),
+                )?);
+            }
+            Self::enforce_coverage(&requirements, &sources, &reports, diagnostics)?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// Resolves the input roots to document.
+    ///
+    /// A single source is documented if `--input-dir` or `input_dir` is given; otherwise the
+    /// `[[sources]]` configured in `config` are documented.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no input directory is given and no sources are configured.
+    fn sources(&self, config: &impl BaselineConfig, output_dir: &Path) -> Result<Vec<Source>> {
+        let prefix = resolve_with_config(self.prefix.clone(), env_vars::PREFIX, config.prefix())
+            .unwrap_or_default();
+
+        let anchor_prefix = resolve_with_config(
+            self.anchor_prefix.clone(),
+            env_vars::ANCHOR_PREFIX,
+            config.anchor_prefix(),
+        )
+        .unwrap_or_default();
+
+        if let Some(input_dir) = self.input_dir.clone().or_else(|| config.input_dir()) {
+            if !config.sources().is_empty() {
+                info!("Ignoring configured sources in favor of the input directory");
+            }
+            return Ok(vec![Source {
+                name: PathBuf::new(),
+                input_dir,
+                output_dir: output_dir.to_path_buf(),
+                category_root: None,
+                prefix,
+                anchor_prefix,
+            }]);
+        }
+
+        let config_dir = config
+            .config_file()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+        let sources: Vec<Source> = config
+            .sources()
+            .into_iter()
+            .map(|source| Source {
+                name: crate::mapping::relative_to(&config_dir, &source.input_dir)
+                    .or_else(|| source.output_subdir.clone())
+                    .or_else(|| source.input_dir.file_name().map(PathBuf::from))
+                    .unwrap_or_default(),
+                output_dir: match &source.output_subdir {
+                    Some(subdir) => output_dir.join(subdir),
+                    None => output_dir.to_path_buf(),
+                },
+                input_dir: source.input_dir,
+                category_root: source.category_root,
+                prefix: source.prefix.unwrap_or_else(|| prefix.clone()),
+                anchor_prefix: source
+                    .anchor_prefix
+                    .unwrap_or_else(|| anchor_prefix.clone()),
+            })
+            .collect();
+        if sources.is_empty() {
+            bail!(
+                "An input directory must be provided with --input-dir or `input_dir`, or configured in [[sources]]"
+            );
+        }
+
+        Ok(sources)
+    }
+
+    /// Resolves the behaviors shared by all commands that process source files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the behaviors are invalid, see [`Behaviors::validate`].
+    fn resolve_behaviors(&self, config: &impl BaselineConfig) -> Result<Behaviors> {
+        let behaviors = self.resolve_unvalidated_behaviors(config)?;
+        behaviors.validate()?;
+        Ok(behaviors)
+    }
+
+    /// Resolves the behaviors shared by all commands that process source files, without checking
+    /// that they're valid.
+    fn resolve_unvalidated_behaviors(&self, config: &impl BaselineConfig) -> Result<Behaviors> {
+        let failure_behavior = resolve_with_config(
+            self.on_failure,
+            env_vars::ON_FAILURE,
+            config.failure_behavior(),
+        );
+
+        let regex_pattern = resolve_with_config(
+            self.regex_pattern.clone(),
+            env_vars::REGEX_PATTERN,
+            config.regex_pattern(),
+        );
+        let mut behaviors = Behaviors::new(failure_behavior, regex_pattern)?;
+        behaviors.on_failure_by_kind = config.failure_behaviors();
+        behaviors
+            .on_failure_by_kind
+            .extend(self.on_failure_for.iter().copied());
+        behaviors.max_failures = resolve_with_config(
+            self.max_failures,
+            env_vars::MAX_FAILURES,
+            config.max_failures(),
+        );
+        behaviors.max_failure_ratio = resolve_with_config(
+            self.max_failure_ratio,
+            env_vars::MAX_FAILURE_RATIO,
+            config.max_failure_ratio(),
+        );
+        behaviors.walk = self.walk_config(config);
+
+        // Logging can only be initialized once the configuration is loaded, so the file it was
+        // loaded from is reported here
+        match (config.config_file(), config.active_profile()) {
+            (Some(path), Some(profile)) => info!(
+                "Using configuration file {} with profile {}",
+                path.display(),
+                profile
+            ),
+            (Some(path), None) => info!("Using configuration file {}", path.display()),
+            (None, _) => info!("No configuration found, falling back to defaults"),
+        }
+
+        Ok(behaviors)
+    }
+
+    /// Merges walk options given on the CLI or in the environment into the configured ones.
+    fn walk_config(&self, config: &impl BaselineConfig) -> WalkConfig {
+        let mut walk = config.walk();
+        if resolve_flag(self.no_gitignore, env_vars::NO_GITIGNORE) {
+            walk.gitignore = Some(false);
+        }
+        walk.ignore_files.extend(self.ignore_file.iter().cloned());
+        if resolve_flag(self.follow_symlinks, env_vars::FOLLOW_SYMLINKS) {
+            walk.follow_symlinks = Some(true);
+        }
+        walk.max_depth = resolve_with_config(self.max_depth, env_vars::MAX_DEPTH, walk.max_depth);
+        if resolve_flag(self.hidden, env_vars::HIDDEN) {
+            walk.hidden = Some(true);
+        }
+        walk
+    }
+
+    /// Prints the effective settings for a source file to stdout as TOML.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file isn't within any input directory or a configuration fragment
+    /// applying to it is invalid.
+    fn show_config(&self, path: &Path, config: &impl BaselineConfig) -> Result<()> {
+        #[derive(serde::Serialize)]
+        struct EffectiveSettings<'a> {
+            #[serde(flatten)]
+            settings: DirectorySettings,
+            failure_behaviors: &'a BTreeMap<ErrorKind, FailureBehavior>,
+            /// Exclude patterns by the directory they're relative to
+            exclude: BTreeMap<String, Vec<String>>,
+        }
+
+        // Nothing is written, so the output directory only determines where sources would go
+        let output_dir = self
+            .output_dir(config)
+            .unwrap_or_else(|_| PathBuf::from("."));
+        let behaviors = self.resolve_behaviors(config)?;
+        let sources = self.sources(config, &output_dir)?;
+        let source = sources
+            .iter()
+            .find(|source| relative_to(&source.input_dir, path).is_some())
+            .with_context(|| format!("{} is not within an input directory", path.display()))?;
+
+        let mut settings = DirectorySettings {
+            prefix: Some(source.prefix.clone()),
+            anchor_prefix: Some(source.anchor_prefix.clone()),
+            failure_behavior: Some(behaviors.on_failure),
+        };
+        let mut exclude = BTreeMap::new();
+        if !config.exclude().is_empty() {
+            exclude.insert(".".to_string(), config.exclude());
+        }
+
+        if let Some(profile) = config.active_profile() {
+            println!("# Profile {}", profile);
+        }
+        for fragment in Fragments::new(&source.input_dir).applying_to(path)? {
+            println!("# Overridden by {}", fragment.path.display());
+            settings.merge(&fragment.settings);
+            if !fragment.exclude.is_empty() {
+                let dir = fragment.path.parent().unwrap_or(&source.input_dir);
+                let dir = relative_to(&source.input_dir, dir).unwrap_or_default();
+                exclude.insert(dir.to_string_lossy().into_owned(), fragment.exclude.clone());
+            }
+        }
+
+        let effective = EffectiveSettings {
+            settings,
+            failure_behaviors: &behaviors.on_failure_by_kind,
+            exclude,
+        };
+        print!(
+            "{}",
+            toml::to_string(&effective).with_context(|| "Failed to serialize settings")?
+        );
+        Ok(())
+    }
+
+    /// Checks the configuration and the fragments within every input directory, reporting all
+    /// problems found to stderr.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration can't be parsed or any problems were found.
+    fn validate_config(&self, config: &impl BaselineConfig) -> Result<()> {
+        let output_dir = self
+            .output_dir(config)
+            .unwrap_or_else(|_| PathBuf::from("."));
+        let behaviors = self.resolve_unvalidated_behaviors(config)?;
+
+        let mut problems: Vec<String> = Vec::new();
+        let mut report = |problem: anyhow::Error| {
+            let problem = format!("{:#}", problem);
+            if !problems.contains(&problem) {
+                problems.push(problem);
+            }
+        };
+
+        if let Err(e) = behaviors.validate() {
+            report(e);
+        }
+        if let Err(e) = self.coverage_requirements(config) {
+            report(e);
+        }
+        if let Err(e) = DocLint::new(&config.lint().severity) {
+            report(e);
+        }
+        // Profiles are checked along with the base configuration, unless one was selected
+        if config.active_profile().is_none() {
+            for profile in config.profiles() {
+                if let Err(e) = self.load_config(Some(&profile)) {
+                    report(e);
+                }
+            }
+        }
+
+        // Input directories are optional here, but are checked when given or configured
+        let sources = if self.input_dir.is_some()
+            || config.input_dir().is_some()
+            || !config.sources().is_empty()
+        {
+            self.sources(config, &output_dir)?
+        } else {
+            Vec::new()
+        };
+        for source in &sources {
+            if !source.input_dir.is_dir() {
+                report(anyhow!(
+                    "Input directory {} does not exist",
+                    source.input_dir.display()
+                ));
+                continue;
+            }
+
+            let fragments = Arc::new(Fragments::new(&source.input_dir));
+            let filter = match path_filter(config, &behaviors, &source.input_dir, &fragments) {
+                Ok(filter) => filter,
+                Err(e) => {
+                    report(e);
+                    continue;
+                }
+            };
+            for entry in filter.walk().filter_map(|entry| entry.ok()) {
+                if entry.file_name() == FRAGMENT_FILE
+                    && entry.path().parent() != Some(source.input_dir.as_path())
+                    && let Err(e) = fragments.applying_to(entry.path())
+                {
+                    report(e);
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            println!("Configuration is valid");
+            return Ok(());
+        }
+        for problem in &problems {
+            eprintln!("error: {}", problem);
+        }
+        bail!("Configuration is invalid ({} problem(s))", problems.len());
+    }
+
+    /// Writes a commented configuration file suited to the input directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration file already exists and `--force` wasn't given.
+    fn init(&self, args: &InitArgs) -> Result<()> {
+        let input_dir = self
+            .input_dir
+            .as_deref()
+            .with_context(|| "An input directory must be provided with --input-dir")?;
+        self.initialize_logging(resolve_option(self.logging_level, env_vars::LOGGING_LEVEL));
+
+        let path = resolve_option(self.config.clone(), env_vars::CONFIG)
+            .unwrap_or_else(|| PathBuf::from(constants::DEFAULT_CONFIG_PATH));
+        if path.exists() && !args.force {
+            bail!(
+                "A configuration file already exists at {}; pass --force to overwrite it",
+                path.display()
+            );
+        }
+
+        let config_dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let survey = Survey::inspect(input_dir, config_dir);
+        std::fs::write(&path, survey.scaffold(input_dir, config_dir))
+            .with_context(|| format!("Failed to write configuration file: {}", path.display()))?;
+        println!("Wrote {}", path.display());
+        Ok(())
+    }
+
+    /// Reports documentation coverage of every source to stdout.
+    ///
+    /// With several sources, file paths are nested under the name of their source.
+    fn coverage(
+        &self,
+        args: &CoverageArgs,
+        config: &MappingConfig,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<()> {
+        // Nothing is written when computing coverage, so the output directory is optional
+        let output_dir = self.output_dir(config).unwrap_or_default();
+        let sources = self.sources(config, &output_dir)?;
+        let mapping_type = self.mapping(config);
+        let behaviors = self.resolve_behaviors(config)?;
+        let requirements = self.coverage_requirements(config)?;
+
+        let mut reports = Vec::new();
+        let mut combined = CoverageReport::default();
+        let mut unmatched = UnmatchedPatterns::default();
+        for source in &sources {
+            let mapping = get_mapping(mapping_type, &source.input_dir, &source.output_dir);
+            let report = self.analyze_coverage(
+                &mapping,
+                config,
+                &behaviors,
+                &source.input_dir,
+                &mut unmatched,
+            )?;
+            combined.extend(&source.name, &report);
+            reports.push(report);
+        }
+        unmatched.warn();
+        print!("{}", combined.render(args.format));
+
+        Self::enforce_coverage(&requirements, &sources, &reports, diagnostics)
+    }
+
+    /// Combines coverage requirements from the CLI, environment and configuration.
+    ///
+    /// Per-glob requirements from the CLI are added to those from the configuration, replacing
+    /// any configured requirement for the same glob.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a requirement is invalid, see [`CoverageConfig::validate`].
+    fn coverage_requirements(&self, config: &impl BaselineConfig) -> Result<CoverageConfig> {
+        let mut requirements = config.coverage();
+        requirements.minimum = resolve_with_config(
+            self.min_coverage,
+            env_vars::MIN_COVERAGE,
+            requirements.minimum,
+        );
+        requirements
+            .directories
+            .extend(self.min_coverage_dir.iter().cloned());
+        requirements
+            .forbid_undocumented
+            .extend(self.forbid_undocumented.iter().cloned());
+        requirements.validate()?;
+        Ok(requirements)
+    }
+
+    /// Fails with the list of offenders if any coverage requirement isn't met by the run.
+    ///
+    /// The minimum applies to all sources together, while globs are relative to the input
+    /// directory of each source.
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` - The checked sources
+    /// * `reports` - The coverage report of each source, in the same order
+    fn enforce_coverage(
+        requirements: &CoverageConfig,
+        sources: &[Source],
+        reports: &[CoverageReport],
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<()> {
+        let reports: Vec<_> = sources
+            .iter()
+            .map(|source| source.input_dir.as_path())
+            .zip(reports)
+            .collect();
+        let violations = requirements.check(&reports)?;
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        eprint!("{}", diagnostic::render(&violations, OutputFormat::Text));
+        let count = violations.len();
+        diagnostics.extend(violations);
+        bail!(
+            "Documentation coverage requirements are not met ({} violation(s))",
+            count
+        );
+    }
+
+    fn analyze_coverage<M: PathMapping>(
+        &self,
+        mapping: &M,
+        config: &M::Config,
+        behaviors: &Behaviors,
+        input_dir: &Path,
+        unmatched: &mut UnmatchedPatterns,
+    ) -> Result<CoverageReport> {
+        let mut report = CoverageReport::default();
+        Self::visit_sources(
+            mapping,
+            config,
+            behaviors,
+            input_dir,
+            unmatched,
+            |path, source| {
+                let relative_path = path.strip_prefix(input_dir).unwrap_or(path);
+                report.add(FileCoverage::new(
+                    relative_path.to_path_buf(),
+                    &NixSource::parse(source),
+                ));
+            },
+        )?;
+
+        Ok(report)
+    }
+
+    /// Reads every source file that documentation would be generated for.
+    ///
+    /// Files are selected by path identification and the path mapping, exactly as during
+    /// generation. Failures are handled according to the configured failure behavior and that of
+    /// any configuration fragments applying to the failing file.
+    ///
+    /// # Arguments
+    ///
+    /// * `unmatched` - Include and exclude patterns that haven't matched any path in the run
+    /// * `visit` - Called with the path and content of every selected source file
+    fn visit_sources<M: PathMapping>(
+        mapping: &M,
+        config: &M::Config,
+        behaviors: &Behaviors,
+        input_dir: &Path,
+        unmatched: &mut UnmatchedPatterns,
+        mut visit: impl FnMut(&Path, &str),
+    ) -> Result<()> {
+        let fragments = Arc::new(Fragments::new(input_dir));
+        let filter = path_filter(config, behaviors, input_dir, &fragments)?;
+        for entry in filter.walk() {
+            let path = match entry {
+                Ok(entry) => entry.into_path(),
+                Err(e) => match behaviors.on_failure_for(ErrorKind::Io, None) {
+                    FailureBehavior::Abort => {
+                        return Err(e).with_context(|| "Failed to list directory");
+                    }
+                    FailureBehavior::Log => {
+                        error!("Failed to list directory: {}", e);
+                        continue;
+                    }
+                    FailureBehavior::Skip => continue,
+                },
+            };
+
+            if path.is_dir()
+                || !behaviors.path_identification.should_process(&path)
+                || !filter.is_included(&path)
+            {
+                continue;
+            }
+
+            let (settings, result) = match fragments.settings_for(&path) {
+                Ok(settings) => {
+                    let result = mapping
+                        .resolve(config, &path)
+                        .and_then(|action| match action {
+                            PathAction::Skip => Ok(None),
+                            PathAction::OutputTo(_) => std::fs::read_to_string(&path)
+                                .map(Some)
+                                .map_err(|source| Error::Io {
+                                    operation: "read source file",
+                                    path: path.clone(),
+                                    source,
+                                }),
+                        });
+                    (settings, result)
+                }
+                Err(e) => (DirectorySettings::default(), Err(fragment_error(&path, &e))),
+            };
+
+            match result {
+                Ok(Some(source)) => visit(&path, &source),
+                Ok(None) => info!("Mapping skipped path {}", path.display()),
+                Err(e) => match behaviors.on_failure_for(e.kind(), settings.failure_behavior) {
+                    FailureBehavior::Abort => {
+                        return Err(e).with_context(|| {
+                            format!("Failed to process source file {}", path.display())
+                        });
+                    }
+                    FailureBehavior::Log => {
+                        error!(
+                            "Failed to process source file {}: {}",
+                            path.display(),
+                            error_chain(&e)
+                        );
+                        continue;
+                    }
+                    FailureBehavior::Skip => continue,
+                },
+            }
+        }
+        unmatched.retain(&filter);
+
+        Ok(())
+    }
+
+    /// Produces additional outputs derived from the generated documentation.
+    ///
+    /// # Arguments
+    ///
+    /// * `generated` - Every generated document, with the source links of the source it was
+    ///   generated from
+    fn post_process(
+        &self,
+        config: &impl BaselineConfig,
+        output_dir: &Path,
+        generated: &[(GeneratedDoc, Option<&SourceLinks>)],
+    ) -> Result<()> {
+        let man_dir =
+            resolve_with_config(self.man_dir.clone(), env_vars::MAN_DIR, config.man_dir());
+        let man_per_function = resolve_flag_with_config(
+            self.man_per_function,
+            env_vars::MAN_PER_FUNCTION,
+            config.man_per_function(),
+        );
+        let search_index = resolve_flag_with_config(
+            self.search_index,
+            env_vars::SEARCH_INDEX,
+            config.search_index(),
+        );
+        let cross_references = resolve_flag_with_config(
+            self.cross_references,
+            env_vars::CROSS_REFERENCES,
+            config.cross_references(),
+        );
+
+        let source_links = generated.iter().any(|(_, links)| links.is_some());
+        if man_dir.is_none() && !search_index && !source_links && !cross_references {
+            return Ok(());
+        }
+
+        let man_pages = man_dir
+            .as_deref()
+            .map(|dir| ManPages::new(dir, man_per_function));
+        let mut index = SearchIndex::default();
+
+        let mut pages = Vec::with_capacity(generated.len());
+        for (doc, links) in generated {
+            let content = std::fs::read_to_string(&doc.destination).with_context(|| {
+                format!(
+                    "Failed to read generated documentation: {}",
+                    doc.destination.display()
+                )
+            })?;
+            let page = DocPage::parse(&content);
+            pages.push((doc, *links, content, page));
+        }
+
+        let mut symbols = SymbolTable::default();
+        if cross_references {
+            for (doc, _, _, page) in &pages {
+                symbols.add_page(&doc.destination, page);
+            }
+        }
+        let mut unresolved = Vec::new();
+
+        for (doc, links, content, page) in &pages {
+            let category = qualified_category(&doc.prefix, &doc.category);
+
+            if let Some(man_pages) = &man_pages {
+                let written = man_pages
+                    .write(&category, page)
+                    .with_context(|| "Failed to generate man pages")?;
+                for path in written {
+                    info!("Wrote man page {}", path.display());
+                }
+            }
+
+            if search_index {
+                let relative_path = doc
+                    .destination
+                    .strip_prefix(output_dir)
+                    .unwrap_or(&doc.destination);
+                index.add_page(&category, relative_path, page);
+            }
+
+            if links.is_some() || cross_references {
+                let mut content = content.clone();
+                if let Some(links) = links {
+                    content = links.inject(&content, &doc.source, &category)?;
+                }
+                if cross_references {
+                    content = symbols.link(&doc.destination, &content, &mut unresolved);
+                }
+
+                std::fs::write(&doc.destination, content).with_context(|| {
+                    format!(
+                        "Failed to rewrite generated documentation: {}",
+                        doc.destination.display()
+                    )
+                })?;
+            }
+        }
+
+        for reference in &unresolved {
+            warn!(
+                "Unresolved reference to {} at {}:{}",
+                reference.name,
+                reference.page.display(),
+                reference.line
+            );
+        }
+        if !unresolved.is_empty() {
+            warn!("{} references could not be resolved", unresolved.len());
+        }
+
+        if search_index {
+            let path = output_dir.join(SEARCH_INDEX_FILE);
+            index.write(&path)?;
+            info!("Wrote search index {}", path.display());
+        }
+
+        Ok(())
+    }
+
+    /// Configures source link generation, if a repository URL or URL template was provided.
+    ///
+    /// Source paths are linked relative to the git repository containing `input_dir`.
+    fn source_links(
+        &self,
+        config: &impl BaselineConfig,
+        input_dir: &Path,
+    ) -> Result<Option<SourceLinks>> {
+        let repo_url =
+            resolve_with_config(self.repo_url.clone(), env_vars::REPO_URL, config.repo_url());
+        let template = resolve_with_config(
+            self.source_url_template.clone(),
+            env_vars::SOURCE_URL_TEMPLATE,
+            config.source_url_template(),
+        );
+        if repo_url.is_none() && template.is_none() {
+            return Ok(None);
+        }
+
+        let root = git::find_root(input_dir);
+        let revision = match resolve_with_config(
+            self.source_rev.clone(),
+            env_vars::SOURCE_REV,
+            config.source_rev(),
+        ) {
+            Some(revision) => revision,
+            None => {
+                let root = root.as_deref().with_context(|| {
+                    "No source revision was provided and the input directory is not within a git repository"
+                })?;
+                git::head_revision(root)
+                    .with_context(|| "Failed to determine source revision from git")?
+            }
+        };
+        let root = match root {
+            Some(root) => root,
+            None => std::fs::canonicalize(input_dir)
+                .with_context(|| "Failed to resolve input directory")?,
+        };
+
+        info!("Generating source links for revision {}", revision);
+        Ok(Some(SourceLinks::new(
+            template.unwrap_or_else(|| DEFAULT_URL_TEMPLATE.to_string()),
+            repo_url.unwrap_or_default(),
+            revision,
+            root,
+        )))
+    }
+
+    /// Checks source doc comments and generated documentation, printing all findings to stdout.
+    fn lint(
+        &self,
+        args: &LintArgs,
+        config: &MappingConfig,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<()> {
+        let output_dir = self.output_dir(config).ok();
+        // Nothing is written when linting, so the output directory is optional
+        let sources = if self.input_dir.is_some()
+            || config.input_dir().is_some()
+            || !config.sources().is_empty()
+        {
+            self.sources(config, output_dir.as_deref().unwrap_or(Path::new("")))?
+        } else {
+            Vec::new()
+        };
+        if sources.is_empty() && output_dir.is_none() {
+            bail!("At least one of --input-dir or --output-dir must be provided");
+        }
+
+        if !sources.is_empty() {
+            let mapping_type = self.mapping(config);
+            let behaviors = self.resolve_behaviors(config)?;
+
+            let mut levels = config.lint().severity;
+            levels.extend(args.severity.iter().cloned());
+            let lint = DocLint::new(&levels)?;
+
+            let mut unmatched = UnmatchedPatterns::default();
+            for source in &sources {
+                let mapping = get_mapping(mapping_type, &source.input_dir, &source.output_dir);
+                Self::visit_sources(
+                    &mapping,
+                    config,
+                    &behaviors,
+                    &source.input_dir,
+                    &mut unmatched,
+                    |path, content| {
+                        diagnostics.extend(lint.check(path, content));
+                    },
+                )?;
+            }
+            unmatched.warn();
+        }
+
+        if let Some(output_dir) = &output_dir {
+            diagnostics.extend(
+                linkcheck::check(output_dir)
+                    .with_context(|| "Failed to check generated documentation")?,
+            );
+        }
+
+        print!("{}", diagnostic::render(diagnostics, args.format));
+
+        let errors = diagnostic::error_count(diagnostics);
+        if errors > 0 {
+            bail!("Lint found {} error(s)", errors);
+        }
+
+        Ok(())
+    }
+
+    /// Initializes logging at the level resolved for the first profile of the run.
+    ///
+    /// Logging can only be initialized once, so differing levels of later profiles are reported
+    /// and ignored.
+    fn initialize_logging_for(&self, configs: &[impl BaselineConfig]) {
+        let levels: Vec<_> = configs
+            .iter()
+            .map(|config| {
+                resolve_with_config(
+                    self.logging_level,
+                    env_vars::LOGGING_LEVEL,
+                    config.logging_level(),
+                )
+            })
+            .collect();
+        let Some(&level) = levels.first() else {
+            return;
+        };
+
+        self.initialize_logging(level);
+        for (config, other) in configs.iter().zip(&levels).skip(1) {
+            if *other != level {
+                warn!(
+                    "Ignoring the logging level of profile {}, as logging is configured by the first profile",
+                    config.active_profile().unwrap_or_default()
+                );
+            }
+        }
+    }
+
+    /// Initializes logging at the given level, or as configured by `RUST_LOG` without one.
+    fn initialize_logging(&self, logging_level: Option<LogLevel>) {
+        let _ = if let Some(level) = logging_level {
+            env_logger::builder().filter_level(level.into()).try_init()
+        } else {
+            env_logger::try_init()
+        };
+    }
+
+    /// Loads the configuration file for a run.
+    ///
+    /// Without a user-provided path, the configuration file is discovered in `search_from` and
+    /// its ancestors up to the repository root, falling back to the current directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The user-provided configuration file, if any
+    /// * `search_from` - The directory that configuration discovery starts from
+    /// * `profile` - The profile to apply to the configuration, if any
+    fn resolve_config<M: PathMapping>(
+        _mapping: &M,
+        path: Option<PathBuf>,
+        search_from: &Path,
+        profile: Option<&str>,
+    ) -> Result<M::Config> {
+        let (path, content) = if let Some(path) = path {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| "Failed to read configuration file from user-provided path")?;
+            (path, content)
+        } else {
+            let discovered = crate::config::discover(search_from, constants::DEFAULT_CONFIG_PATH)
+                .or_else(|| {
+                    let default_config = PathBuf::from(constants::DEFAULT_CONFIG_PATH);
+                    default_config.is_file().then_some(default_config)
+                });
+            let Some(path) = discovered else {
+                if let Some(profile) = profile {
+                    bail!(
+                        "Profile `{}` was selected, but no configuration file was found",
+                        profile
+                    );
+                }
+                return Ok(Default::default());
+            };
+            let content = std::fs::read_to_string(&path).with_context(|| {
+                format!(
+                    "A configuration file exists at {}, but cannot be read",
+                    path.display()
+                )
+            })?;
+            (path, content)
+        };
+
+        let mut config: M::Config = match profile {
+            Some(profile) => crate::config::parse_profile(&path, &content, profile),
+            None => crate::config::parse(&path, &content),
+        }
+        .with_context(|| format!("Failed to parse configuration file {}", path.display()))?;
+        config.set_config_file(&path);
+        if let Some(profile) = profile {
+            config.set_active_profile(profile);
+        }
+        Ok(config)
+    }
+
+    /// Generates documentation for every file of a listed input directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `selected` - Number of files selected for the whole run, which limits failures
+    fn run_in_path<'a, M: PathMapping>(
+        autonixdoc: &AutoNixdoc<'a, M>,
+        config: &M::Config,
+        behaviors: &Behaviors,
+        listing: Listing,
+        selected: usize,
+        diagnostics: &mut Vec<Diagnostic>,
+        report: &mut RunReport,
+    ) -> Result<Vec<GeneratedDoc>> {
+        let mut generated = Vec::new();
+
+        let root = listing.root.as_path();
+        let fragments = &listing.fragments;
+        for entry in listing.entries {
+            let path = match entry {
+                ListedPath::Selected(path) => path,
+                ListedPath::Unselected(path) => {
+                    info!("Skipping uninteresting path {}", path.display());
+                    report.record(FileReport::new(
+                        &path,
+                        FileStatus::SkippedByIdentification,
+                        Duration::ZERO,
+                    ));
+                    continue;
+                }
+                ListedPath::Error(e) => {
+                    let on_failure = behaviors.on_failure_for(ErrorKind::Io, None);
+                    diagnostics.push(generation_failure(root, &e));
+                    report.record(FileReport {
+                        error: Some(e.to_string()),
+                        error_kind: Some(ErrorKind::Io),
+                        on_failure: Some(on_failure),
+                        ..FileReport::new(root, FileStatus::Failed, Duration::ZERO)
+                    });
+                    match on_failure {
+                        FailureBehavior::Abort => {
+                            return Err(e).with_context(|| "Failed to list directory");
+                        }
+                        FailureBehavior::Log => {
+                            error!("Failed to list directory: {}", e);
+                            behaviors.check_failures(report.logged_failures(), selected)?;
+                            continue;
+                        }
+                        FailureBehavior::Skip => continue,
+                    }
+                }
+            };
+
+            let started = Instant::now();
+            info!("Generating documentation for {}", path.display());
+            let (settings, result) = match fragments.settings_for(&path) {
+                Ok(settings) => {
+                    let result = autonixdoc.execute_with(config, &path, &settings);
+                    (settings, result)
+                }
+                Err(e) => (DirectorySettings::default(), Err(fragment_error(&path, &e))),
+            };
+            match result {
+                Ok(Some(doc)) => {
+                    report.record(FileReport {
+                        destination: Some(doc.destination.clone()),
+                        category: Some(doc.category.clone()),
+                        ..FileReport::new(&path, FileStatus::Written, started.elapsed())
+                    });
+                    generated.push(doc);
+                }
+                Ok(None) => {
+                    info!("Mapping skipped path {}", path.display());
+                    report.record(FileReport::new(
+                        &path,
+                        FileStatus::SkippedByMapping,
+                        started.elapsed(),
+                    ));
+                }
+                Err(e) => {
+                    let on_failure = behaviors.on_failure_for(e.kind(), settings.failure_behavior);
+                    diagnostics.push(generation_failure(&path, &error_chain(&e)));
+                    report.record(FileReport {
+                        error: Some(error_chain(&e)),
+                        error_kind: Some(e.kind()),
+                        on_failure: Some(on_failure),
+                        ..FileReport::new(&path, FileStatus::Failed, started.elapsed())
+                    });
+                    match on_failure {
+                        FailureBehavior::Abort => {
+                            return Err(e).with_context(|| {
+                                format!(
+                                    "Documentation generation failed for file {}",
+                                    path.display()
+                                )
+                            });
+                        }
+                        FailureBehavior::Log => {
+                            error!(
+                                "Failed to generate documentation for {}: {}",
+                                path.display(),
+                                error_chain(&e)
+                            );
+                            behaviors.check_failures(report.logged_failures(), selected)?;
+                        }
+                        FailureBehavior::Skip => {}
+                    }
+                }
+            }
+        }
+
+        Ok(generated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_error_chain() {
+        let error = Error::Io {
+            operation: "read source file",
+            path: PathBuf::from("lib/strings.nix"),
+            source: std::io::Error::other("disk on fire"),
+        };
+        assert_eq!(
+            error_chain(&error),
+            "failed to read source file lib/strings.nix: disk on fire"
+        );
+    }
+
+    #[test]
+    fn test_profile_path() {
+        assert_eq!(
+            profile_path(Path::new("out/report.json"), "public"),
+            PathBuf::from("out/report.public.json")
+        );
+        assert_eq!(
+            profile_path(Path::new("junit"), "public"),
+            PathBuf::from("junit.public")
+        );
+    }
+
+    #[test]
+    fn test_path_identification_extension_default() {
+        let identification = PathIdentification::default();
+        assert!(matches!(identification, PathIdentification::NixExtension));
+    }
+
+    #[test]
+    fn test_path_identification_from_pattern_none() {
+        let identification = PathIdentification::from_pattern(None).unwrap();
+        assert!(matches!(identification, PathIdentification::NixExtension));
+    }
+
+    #[test]
+    fn test_path_identification_from_pattern_some() {
+        let identification = PathIdentification::from_pattern(Some(r"\.rs$".to_string())).unwrap();
+        assert!(matches!(identification, PathIdentification::Regex(_)));
+    }
+
+    #[test]
+    fn test_path_identification_from_pattern_invalid_regex() {
+        let result = PathIdentification::from_pattern(Some("[".to_string()));
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid regex pattern")
+        );
+    }
+
+    #[test]
+    fn test_path_identification_extension_should_process_nix() {
+        let identification = PathIdentification::NixExtension;
+        let path = PathBuf::from("/path/to/file.nix");
+        assert!(identification.should_process(&path));
+    }
 
     #[test]
     fn test_path_identification_extension_should_not_process_other() {
@@ -473,4 +2220,75 @@ mod tests {
         let result = Behaviors::new(None, Some("[".to_string()));
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_behaviors_failure_limits() {
+        let mut behaviors = Behaviors::new(None, None).unwrap();
+        let mut report = RunReport::default();
+        for path in ["a.nix", "b.nix", "c.nix"] {
+            report.record(FileReport::new(
+                Path::new(path),
+                FileStatus::Written,
+                Duration::ZERO,
+            ));
+        }
+        let failure = |on_failure| FileReport {
+            error_kind: Some(ErrorKind::NixdocFailed),
+            on_failure: Some(on_failure),
+            ..FileReport::new(Path::new("d.nix"), FileStatus::Failed, Duration::ZERO)
+        };
+        report.record(failure(FailureBehavior::Log));
+
+        assert!(behaviors.check_failures(100, 100).is_ok());
+        assert_eq!(
+            behaviors.outcome(&RunReport::default()).unwrap(),
+            Outcome::Clean
+        );
+        assert_eq!(
+            behaviors.outcome(&report).unwrap(),
+            Outcome::CompletedWithFailures
+        );
+
+        behaviors.max_failures = Some(1);
+        behaviors.max_failure_ratio = Some(0.2);
+        assert!(behaviors.check_failures(1, 5).is_ok());
+        assert!(behaviors.check_failures(2, 20).is_err());
+        assert!(behaviors.check_failures(1, 4).is_err());
+        assert!(behaviors.outcome(&report).is_err());
+
+        let mut skipped = RunReport::default();
+        skipped.record(failure(FailureBehavior::Skip));
+        assert_eq!(behaviors.outcome(&skipped).unwrap(), Outcome::Clean);
+    }
+
+    #[test]
+    fn test_behaviors_on_failure_for() {
+        let mut behaviors = Behaviors::new(Some(FailureBehavior::Log), None).unwrap();
+        behaviors
+            .on_failure_by_kind
+            .insert(ErrorKind::NixdocMissing, FailureBehavior::Abort);
+
+        assert_eq!(
+            behaviors.on_failure_for(ErrorKind::NixdocMissing, Some(FailureBehavior::Skip)),
+            FailureBehavior::Abort
+        );
+        assert_eq!(
+            behaviors.on_failure_for(ErrorKind::NixdocFailed, None),
+            FailureBehavior::Log
+        );
+        assert_eq!(
+            behaviors.on_failure_for(ErrorKind::NixdocFailed, Some(FailureBehavior::Skip)),
+            FailureBehavior::Skip
+        );
+    }
+
+    #[test]
+    fn test_parse_kind_behavior() {
+        assert_eq!(
+            parse_kind_behavior("nixdoc-failed=abort"),
+            Ok((ErrorKind::NixdocFailed, FailureBehavior::Abort))
+        );
+        assert!(parse_kind_behavior("nixdoc-failed").is_err());
+        assert!(parse_kind_behavior("parse-error=abort").is_err());
+    }
 }
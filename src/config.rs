@@ -0,0 +1,364 @@
+//! Parsing and description of configuration files.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use schemars::SchemaGenerator;
+use serde::de::DeserializeOwned;
+
+use crate::mapping::PathMapping;
+
+/// Files and directories marking the root of a repository, above which configuration isn't searched.
+const REPOSITORY_MARKERS: &[&str] = &[".git", "flake.nix"];
+
+/// Minimum similarity between an unknown key or value and an expected one for it to be suggested.
+const SUGGESTION_THRESHOLD: f64 = 0.8;
+
+/// Parses the content of a TOML configuration file.
+///
+/// Configuration types reject unknown keys, so errors are precise: they name the file, line and
+/// column of the offending key or value, and suggest the nearest valid key or value when the
+/// problem looks like a typo.
+///
+/// # Arguments
+///
+/// * `path` - The file that `content` was read from, used in error messages
+/// * `content` - The TOML to parse
+///
+/// # Errors
+///
+/// Returns an error if `content` isn't valid TOML or doesn't match the configuration type.
+pub fn parse<T: DeserializeOwned>(path: &Path, content: &str) -> Result<T> {
+    toml::from_str(content).map_err(|error| {
+        let mut message = error.message().trim_end().to_string();
+        if let Some(suggestion) = suggestion(&message) {
+            message.push_str(&format!("; did you mean `{}`?", suggestion));
+        }
+
+        match error.span() {
+            Some(span) => {
+                let before = &content[..span.start];
+                let line = before.matches('\n').count() + 1;
+                let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+                let column = before[line_start..].chars().count() + 1;
+                anyhow!("{}:{}:{}: {}", path.display(), line, column, message)
+            }
+            None => anyhow!("{}: {}", path.display(), message),
+        }
+    })
+}
+
+/// Parses the content of a TOML configuration file with a named profile applied.
+///
+/// The base configuration is parsed and validated first. The `[profile.<name>]` table is then
+/// merged into it: tables are merged key by key, and all other values are replaced.
+///
+/// # Arguments
+///
+/// * `path` - The file that `content` was read from, used in error messages
+/// * `content` - The TOML to parse
+/// * `profile` - The name of the profile to apply
+///
+/// # Errors
+///
+/// Returns an error if the base configuration is invalid, the profile doesn't exist, or the
+/// configuration with the profile applied doesn't match the configuration type.
+pub fn parse_profile<T: DeserializeOwned>(path: &Path, content: &str, profile: &str) -> Result<T> {
+    parse::<T>(path, content)?;
+    let mut table: toml::Table = parse(path, content)?;
+
+    let no_profiles = toml::Table::new();
+    let profiles = match table.get("profile") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        _ => &no_profiles,
+    };
+    let overrides = match profiles.get(profile) {
+        Some(toml::Value::Table(overrides)) => overrides.clone(),
+        Some(_) => bail!("{}: [profile.{}] must be a table", path.display(), profile),
+        None => {
+            let mut message = format!("{}: unknown profile `{}`", path.display(), profile);
+            match closest(profile, profiles.keys().map(String::as_str)) {
+                Some(suggestion) => message.push_str(&format!("; did you mean `{}`?", suggestion)),
+                None if profiles.is_empty() => message.push_str(", no profiles are configured"),
+                None => {
+                    let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                    message.push_str(&format!(", expected one of {}", names.join(", ")));
+                }
+            }
+            bail!(message);
+        }
+    };
+    if overrides.contains_key("profile") {
+        bail!(
+            "{}: [profile.{}] cannot contain other profiles",
+            path.display(),
+            profile
+        );
+    }
+
+    merge(&mut table, overrides);
+    toml::Value::Table(table)
+        .try_into()
+        .map_err(|error: toml::de::Error| {
+            let mut message = error.message().trim_end().to_string();
+            if let Some(suggestion) = suggestion(&message) {
+                message.push_str(&format!("; did you mean `{}`?", suggestion));
+            }
+            anyhow!("{}: [profile.{}]: {}", path.display(), profile, message)
+        })
+}
+
+/// Merges `overrides` into `base`, merging tables key by key and replacing all other values.
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Finds the configuration file named `file_name` that applies to `start`.
+///
+/// `start` and its ancestors are searched up to the root of the repository containing it, which
+/// is the nearest directory containing `.git` or `flake.nix`, and the nearest file found wins.
+/// Outside of a repository, only `start` itself is searched.
+///
+/// # Arguments
+///
+/// * `start` - The directory that the search starts from, usually the input directory
+/// * `file_name` - The name of the configuration file
+pub fn discover(start: &Path, file_name: &str) -> Option<PathBuf> {
+    let start = std::path::absolute(start).ok()?;
+    let is_root = |dir: &Path| {
+        REPOSITORY_MARKERS
+            .iter()
+            .any(|marker| dir.join(marker).exists())
+    };
+    let searched: Vec<&Path> = match start.ancestors().position(is_root) {
+        Some(root) => start.ancestors().take(root + 1).collect(),
+        None => vec![&start],
+    };
+
+    searched
+        .into_iter()
+        .map(|dir| dir.join(file_name))
+        .find(|path| path.is_file())
+}
+
+/// Renders a JSON Schema describing the configuration file of a path mapping.
+///
+/// The schema includes the documentation, allowed values and defaults of every key, which editors
+/// such as VS Code and taplo use for completion and validation.
+///
+/// # Errors
+///
+/// Returns an error if the schema cannot be serialized.
+pub fn schema<M: PathMapping>(_mapping: &M) -> Result<String> {
+    let schema = SchemaGenerator::default().into_root_schema_for::<M::Config>();
+    serde_json::to_string_pretty(&schema).with_context(|| "Failed to serialize JSON Schema")
+}
+
+/// Suggests the expected key or value nearest to the unknown one named by a serde error message.
+///
+/// Messages have the form ``unknown field `x`, expected one of `a`, `b` `` (or `unknown variant`).
+fn suggestion(message: &str) -> Option<&str> {
+    let rest = message
+        .strip_prefix("unknown field `")
+        .or_else(|| message.strip_prefix("unknown variant `"))?;
+    let (unknown, expected) = rest.split_once('`')?;
+    closest(unknown, expected.split('`').skip(1).step_by(2))
+}
+
+/// Returns the candidate most similar to `unknown`, if any is similar enough to suggest.
+fn closest<'a>(unknown: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let normalize = |s: &str| s.to_lowercase().replace(['_', '-'], "");
+    let unknown = normalize(unknown);
+    candidates
+        .map(|candidate| {
+            (
+                strsim::jaro_winkler(&unknown, &normalize(candidate)),
+                candidate,
+            )
+        })
+        .filter(|(similarity, _)| *similarity >= SUGGESTION_THRESHOLD)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        cli::MappingType,
+        mapping::{AutoMappingConfig, get_mapping},
+    };
+
+    fn error(content: &str) -> String {
+        parse::<AutoMappingConfig>(Path::new("autonixdoc.toml"), content)
+            .err()
+            .expect("configuration should be rejected")
+            .to_string()
+    }
+
+    #[test]
+    fn test_suggestion() {
+        assert_eq!(
+            suggestion("unknown field `ignore_path`, expected one of `ignore_paths`, `include`"),
+            Some("ignore_paths")
+        );
+        assert_eq!(
+            suggestion("unknown variant `log`, expected one of `Abort`, `Log`, `Skip`"),
+            Some("Log")
+        );
+        assert_eq!(
+            suggestion("unknown field `colour`, expected one of `prefix`, `include`"),
+            None
+        );
+        assert_eq!(suggestion("invalid type: integer, expected a string"), None);
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        let message = error("ignore_paths = []\nanchorPrefix = \"lib-\"\n");
+
+        assert!(message.starts_with("autonixdoc.toml:2:1: unknown field `anchorPrefix`"));
+        assert!(message.ends_with("did you mean `anchor_prefix`?"));
+    }
+
+    #[test]
+    fn test_unknown_nested_key() {
+        let message = error("ignore_paths = []\n\n[walk]\nhiden = true\n");
+
+        assert!(message.starts_with("autonixdoc.toml:4:1: unknown field `hiden`"));
+        assert!(message.ends_with("did you mean `hidden`?"));
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let message = error("ignore_paths = []\nlogging_level = \"warning\"\n");
+
+        assert!(message.starts_with("autonixdoc.toml:2:17: unknown variant `warning`"));
+        assert!(message.ends_with("did you mean `warn`?"));
+    }
+
+    #[test]
+    fn test_discover() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let input_dir = root.join("nix/lib");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::write(root.join("autonixdoc.toml"), "").unwrap();
+
+        // Without a repository root, only the starting directory is searched
+        assert_eq!(discover(&input_dir, "autonixdoc.toml"), None);
+
+        fs::write(root.join("flake.nix"), "{ }").unwrap();
+        assert_eq!(
+            discover(&input_dir, "autonixdoc.toml"),
+            Some(root.join("autonixdoc.toml"))
+        );
+
+        fs::write(root.join("nix/autonixdoc.toml"), "").unwrap();
+        assert_eq!(
+            discover(&input_dir, "autonixdoc.toml"),
+            Some(root.join("nix/autonixdoc.toml"))
+        );
+
+        // Nothing above the repository root is considered
+        fs::create_dir_all(root.join("nix/.git")).unwrap();
+        fs::remove_file(root.join("nix/autonixdoc.toml")).unwrap();
+        assert_eq!(discover(&input_dir, "autonixdoc.toml"), None);
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let content = r#"ignore_paths = []
+prefix = "lib"
+exclude = ["tests/"]
+
+[walk]
+hidden = true
+
+[profile.public]
+prefix = "pub"
+exclude = ["tests/", "internal/"]
+
+[profile.public.walk]
+max_depth = 2
+
+[profile.typo]
+prefx = "lib"
+"#;
+        let path = Path::new("autonixdoc.toml");
+
+        let config: AutoMappingConfig = parse_profile(path, content, "public").unwrap();
+        assert_eq!(config.prefix.as_deref(), Some("pub"));
+        assert_eq!(config.exclude, vec!["tests/", "internal/"]);
+        assert_eq!(config.walk.hidden, Some(true));
+        assert_eq!(config.walk.max_depth, Some(2));
+
+        let message = parse_profile::<AutoMappingConfig>(path, content, "publc")
+            .err()
+            .expect("profile should be rejected")
+            .to_string();
+        assert_eq!(
+            message,
+            "autonixdoc.toml: unknown profile `publc`; did you mean `public`?"
+        );
+
+        let message = parse_profile::<AutoMappingConfig>(path, content, "typo")
+            .err()
+            .expect("profile should be rejected")
+            .to_string();
+        assert!(message.starts_with("autonixdoc.toml: [profile.typo]: unknown field `prefx`"));
+        assert!(message.ends_with("did you mean `prefix`?"));
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = schema(&get_mapping(
+            MappingType::Auto,
+            Path::new("."),
+            Path::new("."),
+        ))
+        .unwrap();
+        let schema: serde_json::Value = serde_json::from_str(&schema).unwrap();
+
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(
+            schema["properties"]["prefix"]["description"],
+            "Prefix for generated identifiers"
+        );
+        assert_eq!(schema["properties"]["failure_behavior"]["default"], "Log");
+        assert_eq!(schema["properties"]["mapping"]["default"], "auto");
+        assert!(schema["properties"]["output_dir"].is_object());
+        assert!(schema.get("required").is_none());
+        assert_eq!(
+            schema["$defs"]["FailureBehavior"]["oneOf"][1]["const"],
+            "Log"
+        );
+        assert_eq!(schema["$defs"]["LogLevel"]["enum"][1], "warn");
+        assert_eq!(
+            schema["$defs"]["WalkConfig"]["properties"]["gitignore"]["default"],
+            true
+        );
+    }
+
+    #[test]
+    fn test_valid() {
+        let config: AutoMappingConfig = parse(
+            Path::new("autonixdoc.toml"),
+            "ignore_paths = [\"internal\"]\nlogging_level = \"info\"\n",
+        )
+        .unwrap();
+
+        assert!(config.ignore_paths.contains(&PathBuf::from("internal")));
+    }
+}
@@ -0,0 +1,680 @@
+//! Documentation coverage of the attributes exported by Nix source files.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use globset::{GlobBuilder, GlobMatcher};
+use log::warn;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+
+use crate::{
+    diagnostic::{Diagnostic, OutputFormat, Severity},
+    nixsource::NixSource,
+};
+
+/// Number of documented attributes out of all counted attributes.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    /// Attributes with a non-empty doc comment
+    pub documented: usize,
+    /// All counted attributes
+    pub total: usize,
+}
+
+impl Coverage {
+    /// Percentage of documented attributes; anything without attributes is fully covered.
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.documented as f64 * 100.0 / self.total as f64
+        }
+    }
+
+    fn add(&mut self, other: Coverage) {
+        self.documented += other.documented;
+        self.total += other.total;
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} documented ({:.1}%)",
+            self.documented,
+            self.total,
+            self.percentage()
+        )
+    }
+}
+
+impl Serialize for Coverage {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Coverage", 3)?;
+        state.serialize_field("documented", &self.documented)?;
+        state.serialize_field("total", &self.total)?;
+        state.serialize_field("percentage", &self.percentage())?;
+        state.end()
+    }
+}
+
+/// An exported attribute without documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UndocumentedAttribute {
+    /// Attribute path of the binding
+    pub name: String,
+    /// 1-based line on which the binding starts
+    pub line: usize,
+}
+
+/// Coverage of a single source file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileCoverage {
+    /// Path of the file relative to the input directory
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub coverage: Coverage,
+    /// Exported attributes without documentation, in source order
+    pub undocumented: Vec<UndocumentedAttribute>,
+}
+
+impl FileCoverage {
+    /// Computes the coverage of a scanned source file.
+    ///
+    /// Inherited attributes are documented where they are defined, so they aren't counted.
+    pub fn new(path: PathBuf, source: &NixSource) -> Self {
+        let mut coverage = Coverage::default();
+        let mut undocumented = Vec::new();
+
+        for attribute in source.attributes.iter().filter(|a| !a.inherited) {
+            coverage.total += 1;
+            if attribute.is_documented() {
+                coverage.documented += 1;
+            } else {
+                undocumented.push(UndocumentedAttribute {
+                    name: attribute.name.clone(),
+                    line: attribute.line,
+                });
+            }
+        }
+
+        FileCoverage {
+            path,
+            coverage,
+            undocumented,
+        }
+    }
+}
+
+/// Coverage of a directory, counting every file within it and its subdirectories.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DirectoryCoverage {
+    /// Path of the directory relative to the input directory
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub coverage: Coverage,
+}
+
+/// Coverage of all processed files.
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    files: Vec<FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Adds the coverage of a single file to the report.
+    pub fn add(&mut self, file: FileCoverage) {
+        self.files.push(file);
+    }
+
+    /// Adds every file of another report, with paths nested under `prefix`.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Directory identifying the other report's input directory
+    /// * `other` - The report to add
+    pub fn extend(&mut self, prefix: &Path, other: &CoverageReport) {
+        self.files
+            .extend(other.files.iter().map(|file| FileCoverage {
+                path: prefix.join(&file.path),
+                ..file.clone()
+            }));
+    }
+
+    /// Coverage of every file, ordered by path.
+    pub fn files(&self) -> Vec<&FileCoverage> {
+        let mut files: Vec<_> = self.files.iter().collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+
+    /// Coverage of every directory containing processed files, directly or within its
+    /// subdirectories, ordered by path.
+    pub fn directories(&self) -> Vec<DirectoryCoverage> {
+        let mut directories: BTreeMap<&Path, Coverage> = BTreeMap::new();
+        for file in &self.files {
+            // The first ancestor is the file itself
+            for directory in file.path.ancestors().skip(1) {
+                directories.entry(directory).or_default().add(file.coverage);
+            }
+        }
+
+        directories
+            .into_iter()
+            .map(|(path, coverage)| DirectoryCoverage {
+                path: display_dir(path),
+                coverage,
+            })
+            .collect()
+    }
+
+    /// Coverage across all files.
+    pub fn total(&self) -> Coverage {
+        let mut total = Coverage::default();
+        for file in &self.files {
+            total.add(file.coverage);
+        }
+        total
+    }
+
+    /// Renders the report in the requested format.
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Text => self.render_text(),
+            OutputFormat::Json => {
+                #[derive(Serialize)]
+                struct Json<'a> {
+                    total: Coverage,
+                    directories: Vec<DirectoryCoverage>,
+                    files: Vec<&'a FileCoverage>,
+                }
+
+                let json = Json {
+                    total: self.total(),
+                    directories: self.directories(),
+                    files: self.files(),
+                };
+                serde_json::to_string_pretty(&json).expect("coverage is always serializable") + "\n"
+            }
+        }
+    }
+
+    fn render_text(&self) -> String {
+        let mut output = String::from("Files:\n");
+        for file in self.files() {
+            output.push_str(&format!("  {}: {}\n", file.path.display(), file.coverage));
+            if !file.undocumented.is_empty() {
+                let names: Vec<_> = file.undocumented.iter().map(|a| a.name.as_str()).collect();
+                output.push_str(&format!("    undocumented: {}\n", names.join(", ")));
+            }
+        }
+
+        output.push_str("Directories:\n");
+        for directory in self.directories() {
+            output.push_str(&format!(
+                "  {}: {}\n",
+                directory.path.display(),
+                directory.coverage
+            ));
+        }
+
+        output.push_str(&format!("Total: {}\n", self.total()));
+        output
+    }
+}
+
+/// Documentation coverage requirements, configured in the `[coverage]` table.
+///
+/// Globs are matched against file paths relative to each input directory, with `*` matching
+/// within a single directory and `**` matching across directories.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CoverageConfig {
+    /// Minimum percentage of documented attributes across all files of every input directory
+    pub minimum: Option<f64>,
+    /// Minimum percentage of documented attributes across the files matching each glob
+    #[serde(default)]
+    pub directories: BTreeMap<String, f64>,
+    /// Globs of files in which every exported attribute must be documented
+    #[serde(default)]
+    pub forbid_undocumented: Vec<String>,
+}
+
+impl CoverageConfig {
+    /// Returns true if no requirements are configured.
+    pub fn is_empty(&self) -> bool {
+        self.minimum.is_none() && self.directories.is_empty() && self.forbid_undocumented.is_empty()
+    }
+
+    /// Checks the coverage of every input directory of a run against the configured requirements.
+    ///
+    /// The minimum applies to the coverage across all input directories, while globs are matched
+    /// within each of them. Every unmet requirement is reported as an error diagnostic.
+    ///
+    /// # Arguments
+    ///
+    /// * `reports` - Each input directory, which diagnostic paths are relative to, with the
+    ///   coverage of its processed files
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any configured glob is invalid.
+    pub fn check(&self, reports: &[(&Path, &CoverageReport)]) -> Result<Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let requirement =
+            |rule: &str, path: PathBuf, line: Option<usize>, message: String| Diagnostic {
+                rule: rule.to_string(),
+                severity: Severity::Error,
+                path,
+                line,
+                message,
+            };
+
+        if let Some(minimum) = self.minimum {
+            let mut total = Coverage::default();
+            for (_, report) in reports {
+                total.add(report.total());
+            }
+            if total.percentage() < minimum {
+                let path = match reports {
+                    [(input_dir, _)] => input_dir.to_path_buf(),
+                    _ => PathBuf::from("."),
+                };
+                diagnostics.push(requirement(
+                    "min-coverage",
+                    path,
+                    None,
+                    format!(
+                        "total coverage of {:.1}% is below the required {:.1}%",
+                        total.percentage(),
+                        minimum
+                    ),
+                ));
+            }
+        }
+
+        for (glob, minimum) in &self.directories {
+            let matcher = matcher(glob)?;
+            let mut matched = false;
+            for (input_dir, report) in reports {
+                let mut coverage = Coverage::default();
+                let files: Vec<_> = report
+                    .files
+                    .iter()
+                    .filter(|f| matcher.is_match(&f.path))
+                    .collect();
+                if files.is_empty() {
+                    continue;
+                }
+                matched = true;
+                for file in files {
+                    coverage.add(file.coverage);
+                }
+
+                if coverage.percentage() < *minimum {
+                    diagnostics.push(requirement(
+                        "min-coverage-dir",
+                        input_dir.join(glob),
+                        None,
+                        format!(
+                            "coverage of files matching `{}` is {:.1}%, below the required {:.1}%",
+                            glob,
+                            coverage.percentage(),
+                            minimum
+                        ),
+                    ));
+                }
+            }
+
+            if !matched {
+                warn!("Coverage requirement for `{}` matches no files", glob);
+            }
+        }
+
+        let forbidden = self
+            .forbid_undocumented
+            .iter()
+            .map(|glob| matcher(glob))
+            .collect::<Result<Vec<_>>>()?;
+        for (input_dir, report) in reports {
+            for file in report.files() {
+                if !forbidden.iter().any(|m| m.is_match(&file.path)) {
+                    continue;
+                }
+                for attribute in &file.undocumented {
+                    diagnostics.push(requirement(
+                        "forbid-undocumented",
+                        input_dir.join(&file.path),
+                        Some(attribute.line),
+                        format!(
+                            "`{}` is undocumented, but all attributes in this file must be documented",
+                            attribute.name
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(diagnostics)
+    }
+
+    /// Checks that every required percentage is between 0 and 100 and every glob is valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first invalid requirement.
+    pub fn validate(&self) -> Result<()> {
+        let percentages = self
+            .minimum
+            .iter()
+            .map(|minimum| ("minimum", minimum))
+            .chain(self.directories.iter().map(|(glob, m)| (glob.as_str(), m)));
+        for (requirement, percentage) in percentages {
+            if !(0.0..=100.0).contains(percentage) {
+                bail!(
+                    "Coverage requirement `{}` of {} is not a percentage between 0 and 100",
+                    requirement,
+                    percentage
+                );
+            }
+        }
+
+        for glob in self.directories.keys().chain(&self.forbid_undocumented) {
+            matcher(glob)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compiles a coverage glob, in which only `**` matches across directory separators.
+fn matcher(glob: &str) -> Result<GlobMatcher> {
+    Ok(GlobBuilder::new(glob)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("Invalid coverage glob: {}", glob))?
+        .compile_matcher())
+}
+
+/// The input directory itself is displayed as `.` rather than an empty path.
+fn display_dir(path: &Path) -> PathBuf {
+    if path.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"{ lib }:
+let
+  inherit (lib) id;
+in
+{
+  /**
+    Documented
+  */
+  documented = x: x;
+
+  # Not a doc comment
+  lineComment = x: x;
+
+  /** */
+  emptyDoc = x: x;
+
+  inherit id;
+}
+"#;
+
+    fn report() -> CoverageReport {
+        let source = NixSource::parse(SOURCE);
+        let mut report = CoverageReport::default();
+        report.add(FileCoverage::new(
+            PathBuf::from("text/strings.nix"),
+            &source,
+        ));
+        report.add(FileCoverage::new(PathBuf::from("lists.nix"), &source));
+        report.add(FileCoverage::new(
+            PathBuf::from("text/empty.nix"),
+            &NixSource::default(),
+        ));
+        report
+    }
+
+    #[test]
+    fn test_file_coverage() {
+        let file = FileCoverage::new(PathBuf::from("strings.nix"), &NixSource::parse(SOURCE));
+
+        assert_eq!(
+            file.coverage,
+            Coverage {
+                documented: 1,
+                total: 3
+            }
+        );
+        let names: Vec<_> = file.undocumented.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["lineComment", "emptyDoc"]);
+        assert_eq!(file.undocumented[0].line, 12);
+    }
+
+    #[test]
+    fn test_report_extend() {
+        let mut combined = report();
+        combined.extend(Path::new("pkgs"), &report());
+
+        let files = combined.files();
+        assert_eq!(files.len(), 6);
+        assert_eq!(files[0].path, PathBuf::from("lists.nix"));
+        assert_eq!(files[1].path, PathBuf::from("pkgs/lists.nix"));
+        assert_eq!(combined.total().total, 12);
+    }
+
+    #[test]
+    fn test_directories_include_subdirectories() {
+        let mut nested = CoverageReport::default();
+        nested.extend(Path::new("pkgs/text"), &report());
+
+        let directories: Vec<_> = nested
+            .directories()
+            .into_iter()
+            .map(|d| (d.path, d.coverage.total))
+            .collect();
+        assert_eq!(
+            directories,
+            vec![
+                (PathBuf::from("."), 6),
+                (PathBuf::from("pkgs"), 6),
+                (PathBuf::from("pkgs/text"), 6),
+                (PathBuf::from("pkgs/text/text"), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_percentage() {
+        assert_eq!(Coverage::default().percentage(), 100.0);
+        assert_eq!(
+            Coverage {
+                documented: 1,
+                total: 4
+            }
+            .percentage(),
+            25.0
+        );
+    }
+
+    #[test]
+    fn test_report_aggregates() {
+        let report = report();
+
+        let paths: Vec<_> = report.files().iter().map(|f| f.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("lists.nix"),
+                PathBuf::from("text/empty.nix"),
+                PathBuf::from("text/strings.nix"),
+            ]
+        );
+
+        let directories = report.directories();
+        assert_eq!(directories.len(), 2);
+        assert_eq!(directories[0].path, PathBuf::from("."));
+        assert_eq!(directories[0].coverage.total, 6);
+        assert_eq!(directories[1].path, PathBuf::from("text"));
+        assert_eq!(directories[1].coverage.total, 3);
+
+        assert_eq!(
+            report.total(),
+            Coverage {
+                documented: 2,
+                total: 6
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_text() {
+        let text = report().render(OutputFormat::Text);
+
+        assert!(text.contains("  text/strings.nix: 1/3 documented (33.3%)\n"));
+        assert!(text.contains("    undocumented: lineComment, emptyDoc\n"));
+        assert!(text.contains("  text/empty.nix: 0/0 documented (100.0%)\n"));
+        assert!(text.ends_with("Total: 2/6 documented (33.3%)\n"));
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json = report().render(OutputFormat::Json);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["total"]["documented"], 2);
+        assert_eq!(value["total"]["total"], 6);
+        assert_eq!(value["directories"][0]["path"], ".");
+        assert_eq!(value["files"][2]["path"], "text/strings.nix");
+        assert_eq!(value["files"][2]["undocumented"][1]["name"], "emptyDoc");
+    }
+
+    #[test]
+    fn test_check_requirements() {
+        let mut directories = BTreeMap::new();
+        directories.insert("text/**".to_string(), 50.0);
+        directories.insert("*.nix".to_string(), 10.0);
+        directories.insert("missing/**".to_string(), 100.0);
+        let config = CoverageConfig {
+            minimum: Some(50.0),
+            directories,
+            forbid_undocumented: vec!["lists.nix".to_string()],
+        };
+
+        let diagnostics = config.check(&[(Path::new("lib"), &report())]).unwrap();
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.rule.as_str(), d.path.clone(), d.line))
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                ("min-coverage", PathBuf::from("lib"), None),
+                ("min-coverage-dir", PathBuf::from("lib/text/**"), None),
+                (
+                    "forbid-undocumented",
+                    PathBuf::from("lib/lists.nix"),
+                    Some(12)
+                ),
+                (
+                    "forbid-undocumented",
+                    PathBuf::from("lib/lists.nix"),
+                    Some(15)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_minimum_across_input_dirs() {
+        let config = CoverageConfig {
+            minimum: Some(30.0),
+            ..Default::default()
+        };
+        let mut documented = CoverageReport::default();
+        documented.add(FileCoverage {
+            path: PathBuf::from("strings.nix"),
+            coverage: Coverage {
+                documented: 2,
+                total: 2,
+            },
+            undocumented: Vec::new(),
+        });
+
+        // lists.nix alone is 33.3% covered, but counting strings.nix too it's 50%
+        let reports = [
+            (Path::new("lib"), &report()),
+            (Path::new("modules"), &documented),
+        ];
+        assert!(config.check(&reports).unwrap().is_empty());
+
+        let config = CoverageConfig {
+            minimum: Some(60.0),
+            ..config
+        };
+        let diagnostics = config.check(&reports).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, PathBuf::from("."));
+    }
+
+    #[test]
+    fn test_glob_matches_within_directory() {
+        let config = CoverageConfig {
+            forbid_undocumented: vec!["*.nix".to_string()],
+            ..Default::default()
+        };
+
+        let diagnostics = config.check(&[(Path::new("lib"), &report())]).unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.path == Path::new("lib/lists.nix"))
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = CoverageConfig {
+            minimum: Some(80.0),
+            directories: BTreeMap::from([("strings/**".to_string(), 100.0)]),
+            forbid_undocumented: vec!["*.nix".to_string()],
+        };
+        assert!(config.validate().is_ok());
+
+        config.minimum = Some(120.0);
+        assert!(config.validate().is_err());
+
+        config.minimum = None;
+        config.forbid_undocumented.push("{a,b".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_check_without_requirements() {
+        let config = CoverageConfig::default();
+
+        assert!(config.is_empty());
+        assert!(
+            config
+                .check(&[(Path::new("lib"), &report())])
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Diagnostics reported by checks over sources and generated documentation.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// How severe a reported problem is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The problem should be looked at, but doesn't fail checks
+    Warning,
+    /// The problem fails checks
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A single problem found by a check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Identifier of the rule that produced the diagnostic
+    pub rule: String,
+    /// How severe the problem is
+    pub severity: Severity,
+    /// File in which the problem was found
+    pub path: PathBuf,
+    /// 1-based line at which the problem was found, if applicable
+    pub line: Option<usize>,
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(f, ": {}[{}]: {}", self.severity, self.rule, self.message)
+    }
+}
+
+/// Output formats for reported diagnostics.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Default)]
+pub enum OutputFormat {
+    /// One human-readable line per diagnostic
+    #[default]
+    Text,
+    /// A JSON array of diagnostic objects
+    Json,
+}
+
+/// Renders diagnostics in the requested format.
+pub fn render(diagnostics: &[Diagnostic], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => diagnostics
+            .iter()
+            .map(|d| format!("{}\n", d))
+            .collect::<String>(),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(diagnostics).expect("diagnostics are always serializable")
+                + "\n"
+        }
+    }
+}
+
+/// Renders diagnostics as a SARIF 2.1.0 log with a single run.
+///
+/// Absolute paths within the current directory are made relative to it, matching how SARIF
+/// consumers resolve artifact locations against the repository checkout.
+pub fn sarif(diagnostics: &[Diagnostic]) -> String {
+    let mut rules: Vec<&str> = diagnostics.iter().map(|d| d.rule.as_str()).collect();
+    rules.sort();
+    rules.dedup();
+
+    let results: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            let mut location = json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": display_path(&d.path) },
+                },
+            });
+            if let Some(line) = d.line {
+                location["physicalLocation"]["region"] = json!({ "startLine": line });
+            }
+
+            json!({
+                "ruleId": d.rule,
+                "level": d.severity.to_string(),
+                "message": { "text": d.message },
+                "locations": [location],
+            })
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    });
+
+    serde_json::to_string_pretty(&log).expect("SARIF logs are always serializable") + "\n"
+}
+
+/// Renders diagnostics as GitHub Actions workflow commands, which annotate pull requests.
+pub fn github_annotations(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|d| {
+            let mut properties = format!("file={}", escape_property(&display_path(&d.path)));
+            if let Some(line) = d.line {
+                properties.push_str(&format!(",line={}", line));
+            }
+            properties.push_str(&format!(",title={}", escape_property(&d.rule)));
+            format!(
+                "::{} {}::{}\n",
+                d.severity,
+                properties,
+                escape_data(&d.message)
+            )
+        })
+        .collect()
+}
+
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Formats a path with forward slashes, relative to the current directory when possible.
+fn display_path(path: &Path) -> String {
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path);
+
+    relative
+        .to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "/")
+}
+
+/// Returns the number of diagnostics with error severity.
+pub fn error_count(diagnostics: &[Diagnostic]) -> usize {
+    diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(severity: Severity, line: Option<usize>) -> Diagnostic {
+        Diagnostic {
+            rule: "test-rule".to_string(),
+            severity,
+            path: PathBuf::from("docs/strings.md"),
+            line,
+            message: "something is wrong".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_text() {
+        let diagnostics = vec![
+            diagnostic(Severity::Error, Some(3)),
+            diagnostic(Severity::Warning, None),
+        ];
+
+        assert_eq!(
+            render(&diagnostics, OutputFormat::Text),
+            "docs/strings.md:3: error[test-rule]: something is wrong\n\
+             docs/strings.md: warning[test-rule]: something is wrong\n"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json = render(&[diagnostic(Severity::Error, Some(3))], OutputFormat::Json);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value[0]["rule"], "test-rule");
+        assert_eq!(value[0]["severity"], "error");
+        assert_eq!(value[0]["path"], "docs/strings.md");
+        assert_eq!(value[0]["line"], 3);
+    }
+
+    #[test]
+    fn test_sarif() {
+        let sarif = sarif(&[
+            diagnostic(Severity::Error, Some(3)),
+            diagnostic(Severity::Warning, None),
+        ]);
+        let value: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+
+        assert_eq!(value["version"], "2.1.0");
+        let run = &value["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "autonixdoc");
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "test-rule");
+        assert_eq!(run["results"][0]["ruleId"], "test-rule");
+        assert_eq!(run["results"][0]["level"], "error");
+        assert_eq!(run["results"][0]["message"]["text"], "something is wrong");
+
+        let location = &run["results"][0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "docs/strings.md");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert!(
+            run["results"][1]["locations"][0]["physicalLocation"]
+                .get("region")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_github_annotations() {
+        let mut escaped = diagnostic(Severity::Warning, None);
+        escaped.message = "100% wrong,\nreally".to_string();
+        escaped.path = PathBuf::from("docs/a,b.md");
+
+        assert_eq!(
+            github_annotations(&[diagnostic(Severity::Error, Some(3)), escaped]),
+            "::error file=docs/strings.md,line=3,title=test-rule::something is wrong\n\
+             ::warning file=docs/a%2Cb.md,title=test-rule::100%25 wrong,%0Areally\n"
+        );
+    }
+
+    #[test]
+    fn test_error_count() {
+        let diagnostics = vec![
+            diagnostic(Severity::Error, None),
+            diagnostic(Severity::Warning, None),
+            diagnostic(Severity::Error, None),
+        ];
+
+        assert_eq!(error_count(&diagnostics), 2);
+    }
+}
@@ -0,0 +1,343 @@
+//! Checks for common mistakes when authoring doc comments in Nix source files.
+
+use std::{collections::BTreeMap, path::Path, str::FromStr, sync::LazyLock};
+
+use anyhow::{Result, bail};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{
+    diagnostic::{Diagnostic, Severity},
+    markdown::is_code_fence,
+    nixsource::{CommentKind, NixSource},
+};
+
+/// An attribute is documented with a `#` comment rather than a `/** */` doc comment.
+pub const LINE_COMMENT_DOC: &str = "line-comment-doc";
+/// An attribute is documented with a `/* */` comment rather than a `/** */` doc comment.
+pub const BLOCK_COMMENT_DOC: &str = "block-comment-doc";
+/// A doc comment has no content.
+pub const EMPTY_DOC: &str = "empty-doc";
+/// A doc comment section heading (e.g. `# Type`) is malformed.
+pub const SECTION_FORMAT: &str = "section-format";
+
+/// All rules along with their default levels.
+const RULES: &[(&str, RuleLevel)] = &[
+    (LINE_COMMENT_DOC, RuleLevel::Warning),
+    (BLOCK_COMMENT_DOC, RuleLevel::Warning),
+    (EMPTY_DOC, RuleLevel::Error),
+    (SECTION_FORMAT, RuleLevel::Error),
+];
+
+/// Section headings understood by nixdoc.
+const SECTIONS: &[&str] = &["Type", "Inputs", "Arguments", "Example", "Examples"];
+
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(#+)(\s*)(\S.*?)\s*$").expect("valid regex"));
+
+static LEGACY_SECTION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([A-Za-z]+):\s*$").expect("valid regex"));
+
+/// How findings of a rule are reported.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    /// The rule is disabled
+    Off,
+    /// Findings are reported as warnings
+    Warning,
+    /// Findings are reported as errors
+    Error,
+}
+
+impl RuleLevel {
+    fn severity(self) -> Option<Severity> {
+        match self {
+            Self::Off => None,
+            Self::Warning => Some(Severity::Warning),
+            Self::Error => Some(Severity::Error),
+        }
+    }
+}
+
+impl FromStr for RuleLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            _ => Err(format!(
+                "invalid rule level `{}`, expected one of: off, warning, error",
+                s
+            )),
+        }
+    }
+}
+
+/// Doc-comment lint configuration, configured in the `[lint]` table.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+    /// Levels of individual rules, overriding their defaults
+    #[serde(default)]
+    pub severity: BTreeMap<String, RuleLevel>,
+}
+
+/// Lints doc comments with a fixed set of rule levels.
+#[derive(Debug, Clone)]
+pub struct DocLint {
+    levels: BTreeMap<&'static str, RuleLevel>,
+}
+
+impl DocLint {
+    /// Creates a linter using the default level of every rule, overridden by `overrides`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an override names an unknown rule.
+    pub fn new(overrides: &BTreeMap<String, RuleLevel>) -> Result<Self> {
+        let mut levels: BTreeMap<&'static str, RuleLevel> = RULES.iter().copied().collect();
+        for (rule, level) in overrides {
+            match RULES.iter().find(|(name, _)| name == rule) {
+                Some((name, _)) => {
+                    levels.insert(name, *level);
+                }
+                None => bail!(
+                    "Unknown lint rule `{}`, expected one of: {}",
+                    rule,
+                    RULES
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+        }
+
+        Ok(DocLint { levels })
+    }
+
+    /// Checks the doc comments of every attribute exported by a source file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the source file, used for reported diagnostics
+    /// * `source` - Content of the source file
+    pub fn check(&self, path: &Path, source: &str) -> Vec<Diagnostic> {
+        let source = NixSource::parse(source);
+        let mut diagnostics = Vec::new();
+        let mut report = |rule: &'static str, line: usize, message: String| {
+            if let Some(severity) = self.levels[rule].severity() {
+                diagnostics.push(Diagnostic {
+                    rule: rule.to_string(),
+                    severity,
+                    path: path.to_path_buf(),
+                    line: Some(line),
+                    message,
+                });
+            }
+        };
+
+        for attribute in source.attributes.iter().filter(|a| !a.inherited) {
+            let Some(comment) = &attribute.comment else {
+                continue;
+            };
+
+            match comment.kind {
+                CommentKind::Line => report(
+                    LINE_COMMENT_DOC,
+                    comment.line,
+                    format!(
+                        "`{}` is documented with a `#` comment, which nixdoc ignores; use `/** */`",
+                        attribute.name
+                    ),
+                ),
+                CommentKind::Block => report(
+                    BLOCK_COMMENT_DOC,
+                    comment.line,
+                    format!(
+                        "`{}` is documented with a `/* */` comment, which nixdoc ignores; use `/** */`",
+                        attribute.name
+                    ),
+                ),
+                CommentKind::Doc if comment.text.trim().is_empty() => report(
+                    EMPTY_DOC,
+                    comment.line,
+                    format!("doc comment of `{}` is empty", attribute.name),
+                ),
+                CommentKind::Doc => {
+                    let mut in_fence = false;
+                    for (offset, line) in comment.text.lines().enumerate() {
+                        if is_code_fence(line) {
+                            in_fence = !in_fence;
+                            continue;
+                        }
+                        if in_fence {
+                            continue;
+                        }
+                        if let Some(message) = section_problem(line.trim()) {
+                            report(SECTION_FORMAT, comment.line + offset, message);
+                        }
+                    }
+                }
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// Describes what is wrong with a line that looks like a section heading, if anything.
+fn section_problem(line: &str) -> Option<String> {
+    if let Some(captures) = HEADING.captures(line) {
+        let level = captures[1].len();
+        let name = &captures[3];
+        let section = SECTIONS.iter().find(|s| s.eq_ignore_ascii_case(name))?;
+
+        if captures[2].is_empty() {
+            Some(format!(
+                "section heading `{}` needs a space after `#`",
+                line
+            ))
+        } else if level != 1 || name != *section {
+            Some(format!(
+                "section heading `{}` should be written as `# {}`",
+                line, section
+            ))
+        } else {
+            None
+        }
+    } else if let Some(captures) = LEGACY_SECTION.captures(line) {
+        let name = &captures[1];
+        SECTIONS
+            .iter()
+            .find(|s| s.eq_ignore_ascii_case(name))
+            .map(|section| {
+                format!(
+                    "`{}` is not recognized as a section; use the heading `# {}`",
+                    line, section
+                )
+            })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"{ lib }:
+{
+  # Line comment
+  line = x: x;
+
+  /* Block comment */
+  block = x: x;
+
+  /** */
+  empty = x: x;
+
+  /**
+    Well formed
+
+    # Type
+
+    ```nix
+    # examples
+    good :: a -> a
+    ```
+  */
+  good = x: x;
+
+  /**
+    Malformed
+
+    ## Type
+    #Example
+    # examples
+    Arguments:
+  */
+  malformed = x: x;
+
+  undocumented = x: x;
+}
+"#;
+
+    fn found(diagnostics: &[Diagnostic]) -> Vec<(&str, Option<usize>, Severity)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.rule.as_str(), d.line, d.severity))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_default_levels() {
+        let lint = DocLint::new(&BTreeMap::new()).unwrap();
+        let diagnostics = lint.check(Path::new("lib/strings.nix"), SOURCE);
+
+        assert_eq!(
+            found(&diagnostics),
+            vec![
+                (LINE_COMMENT_DOC, Some(3), Severity::Warning),
+                (BLOCK_COMMENT_DOC, Some(6), Severity::Warning),
+                (EMPTY_DOC, Some(9), Severity::Error),
+                (SECTION_FORMAT, Some(27), Severity::Error),
+                (SECTION_FORMAT, Some(28), Severity::Error),
+                (SECTION_FORMAT, Some(29), Severity::Error),
+                (SECTION_FORMAT, Some(30), Severity::Error),
+            ]
+        );
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.path == Path::new("lib/strings.nix"))
+        );
+    }
+
+    #[test]
+    fn test_check_overridden_levels() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert(LINE_COMMENT_DOC.to_string(), RuleLevel::Error);
+        overrides.insert(SECTION_FORMAT.to_string(), RuleLevel::Off);
+        let lint = DocLint::new(&overrides).unwrap();
+
+        assert_eq!(
+            found(&lint.check(Path::new("strings.nix"), SOURCE)),
+            vec![
+                (LINE_COMMENT_DOC, Some(3), Severity::Error),
+                (BLOCK_COMMENT_DOC, Some(6), Severity::Warning),
+                (EMPTY_DOC, Some(9), Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_rule() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("no-such-rule".to_string(), RuleLevel::Error);
+
+        assert!(DocLint::new(&overrides).is_err());
+    }
+
+    #[test]
+    fn test_section_problem() {
+        assert_eq!(section_problem("# Type"), None);
+        assert_eq!(section_problem("# Some other heading"), None);
+        assert_eq!(section_problem("Note:"), None);
+        assert!(section_problem("#Type").is_some());
+        assert!(section_problem("### Example").is_some());
+        assert!(section_problem("# type").is_some());
+        assert!(section_problem("Example:").is_some());
+    }
+
+    #[test]
+    fn test_rule_level_from_str() {
+        assert_eq!("Error".parse::<RuleLevel>(), Ok(RuleLevel::Error));
+        assert_eq!("off".parse::<RuleLevel>(), Ok(RuleLevel::Off));
+        assert!("fatal".parse::<RuleLevel>().is_err());
+    }
+}
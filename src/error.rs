@@ -0,0 +1,200 @@
+//! Typed errors returned by documentation generation and path mapping.
+
+use std::{fmt, io, path::PathBuf};
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Errors that can occur while generating documentation for a single source file.
+#[derive(Debug)]
+pub enum Error {
+    /// The nixdoc executable could not be found
+    NixdocMissing,
+    /// nixdoc ran, but exited unsuccessfully
+    NixdocFailed {
+        /// The source file being documented
+        path: PathBuf,
+        /// Exit code of nixdoc, if it exited normally
+        status: Option<i32>,
+        /// Everything nixdoc wrote to stderr
+        stderr: String,
+    },
+    /// A source path is not located within the input directory
+    OutsideInputDir {
+        /// The offending source path
+        path: PathBuf,
+        /// The input directory that the path was expected to be within
+        input_dir: PathBuf,
+    },
+    /// The path mapping could not map a source path
+    MappingFailed {
+        /// The source path being mapped
+        path: PathBuf,
+        /// Why mapping failed
+        reason: String,
+    },
+    /// A file could not be read or written
+    Io {
+        /// What was being attempted (e.g. "create output file")
+        operation: &'static str,
+        /// The file or directory involved
+        path: PathBuf,
+        /// The underlying error
+        source: io::Error,
+    },
+    /// A source path is not valid Unicode, which nixdoc requires
+    InvalidUnicode {
+        /// The offending source path
+        path: PathBuf,
+    },
+    /// A configuration fragment applying to a source path could not be loaded
+    InvalidFragment {
+        /// The source path the fragment applies to
+        path: PathBuf,
+        /// Why the fragment could not be loaded
+        reason: String,
+    },
+}
+
+/// Result type for operations returning a typed [Error].
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Returns the kind of the error, used to select how its failure is handled.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::NixdocMissing => ErrorKind::NixdocMissing,
+            Self::NixdocFailed { .. } => ErrorKind::NixdocFailed,
+            Self::OutsideInputDir { .. } => ErrorKind::OutsideInputDir,
+            Self::MappingFailed { .. } => ErrorKind::MappingFailed,
+            Self::Io { .. } => ErrorKind::Io,
+            Self::InvalidUnicode { .. } => ErrorKind::InvalidUnicode,
+            Self::InvalidFragment { .. } => ErrorKind::InvalidFragment,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NixdocMissing => write!(f, "nixdoc could not be found on the PATH"),
+            Self::NixdocFailed {
+                path,
+                status,
+                stderr,
+            } => {
+                write!(f, "nixdoc failed for {}", path.display())?;
+                if let Some(status) = status {
+                    write!(f, " with exit code {}", status)?;
+                }
+                write!(f, ": {}", stderr.trim_end())
+            }
+            Self::OutsideInputDir { path, input_dir } => write!(
+                f,
+                "{} is not within the input directory {}",
+                path.display(),
+                input_dir.display()
+            ),
+            Self::MappingFailed { path, reason } => {
+                write!(f, "path mapping failed for {}: {}", path.display(), reason)
+            }
+            Self::Io {
+                operation, path, ..
+            } => write!(f, "failed to {} {}", operation, path.display()),
+            Self::InvalidUnicode { path } => {
+                write!(f, "source path {} is not valid Unicode", path.display())
+            }
+            Self::InvalidFragment { path, reason } => write!(
+                f,
+                "invalid configuration fragment for {}: {}",
+                path.display(),
+                reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Kinds of [Error], for which failure behavior can be configured individually.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ValueEnum,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    /// nixdoc could not be found
+    NixdocMissing,
+    /// nixdoc exited unsuccessfully, usually because a source file couldn't be parsed
+    NixdocFailed,
+    /// A source path is outside the input directory
+    OutsideInputDir,
+    /// The path mapping failed
+    MappingFailed,
+    /// A file or directory could not be read or written
+    Io,
+    /// A source path is not valid Unicode
+    InvalidUnicode,
+    /// A configuration fragment could not be loaded
+    InvalidFragment,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = Error::NixdocFailed {
+            path: PathBuf::from("lib/strings.nix"),
+            status: Some(1),
+            stderr: "error: unexpected token\n".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "nixdoc failed for lib/strings.nix with exit code 1: error: unexpected token"
+        );
+        assert_eq!(error.kind(), ErrorKind::NixdocFailed);
+
+        let error = Error::Io {
+            operation: "create output file",
+            path: PathBuf::from("docs/strings.md"),
+            source: io::Error::from(io::ErrorKind::PermissionDenied),
+        };
+        assert_eq!(
+            error.to_string(),
+            "failed to create output file docs/strings.md"
+        );
+        assert!(std::error::Error::source(&error).is_some());
+        assert_eq!(error.kind(), ErrorKind::Io);
+    }
+
+    #[test]
+    fn test_kind_deserialize() {
+        #[derive(Deserialize)]
+        struct Config {
+            kind: ErrorKind,
+        }
+
+        let config: Config = toml::from_str("kind = \"nixdoc-missing\"").unwrap();
+        assert_eq!(config.kind, ErrorKind::NixdocMissing);
+    }
+}
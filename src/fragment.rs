@@ -0,0 +1,303 @@
+//! Per-directory configuration fragments overriding settings for a subtree of an input directory.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result, bail};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::{Deserialize, Serialize, de::IgnoredAny};
+
+use crate::{cli::FailureBehavior, config, mapping::relative_to};
+
+/// Name of the configuration fragments placed in subdirectories of an input directory.
+pub const FRAGMENT_FILE: &str = "autonixdoc.toml";
+
+/// Settings that a configuration fragment can override for its subtree.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DirectorySettings {
+    /// Prefix for generated identifiers
+    pub prefix: Option<String>,
+    /// Prefix for anchor links
+    pub anchor_prefix: Option<String>,
+    /// How failures to document files in the subtree are handled
+    pub failure_behavior: Option<FailureBehavior>,
+}
+
+impl DirectorySettings {
+    /// Overrides these settings with every setting that is set in `other`.
+    pub fn merge(&mut self, other: &DirectorySettings) {
+        if other.prefix.is_some() {
+            self.prefix.clone_from(&other.prefix);
+        }
+        if other.anchor_prefix.is_some() {
+            self.anchor_prefix.clone_from(&other.anchor_prefix);
+        }
+        self.failure_behavior = other.failure_behavior.or(self.failure_behavior);
+    }
+}
+
+/// Contents of a configuration fragment file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FragmentFile {
+    prefix: Option<String>,
+    anchor_prefix: Option<String>,
+    failure_behavior: Option<FailureBehavior>,
+    /// Accepted only to explain why it can't be overridden
+    description: Option<IgnoredAny>,
+    /// Gitignore-style patterns relative to the directory containing the fragment
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+/// A configuration fragment loaded from a subdirectory of an input directory.
+#[derive(Debug)]
+pub struct Fragment {
+    /// Path to the fragment file
+    pub path: PathBuf,
+    /// Settings overridden for the subtree
+    pub settings: DirectorySettings,
+    /// Gitignore-style patterns of files and directories to leave out of the subtree
+    pub exclude: Vec<String>,
+    matcher: Gitignore,
+}
+
+impl Fragment {
+    fn load(dir: &Path, path: PathBuf) -> Result<Self> {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read configuration fragment {}", path.display()))?;
+        let file: FragmentFile = config::parse(&path, &content)
+            .with_context(|| "Failed to parse configuration fragment")?;
+        if file.description.is_some() {
+            bail!(
+                "`description` can't be overridden in {}: descriptions are always taken from the \
+                 second line of each file",
+                path.display()
+            );
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        for pattern in &file.exclude {
+            builder.add_line(None, pattern).with_context(|| {
+                format!(
+                    "Invalid exclude pattern `{}` in {}",
+                    pattern,
+                    path.display()
+                )
+            })?;
+        }
+
+        Ok(Fragment {
+            matcher: builder
+                .build()
+                .with_context(|| format!("Invalid exclude patterns in {}", path.display()))?,
+            path,
+            settings: DirectorySettings {
+                prefix: file.prefix,
+                anchor_prefix: file.anchor_prefix,
+                failure_behavior: file.failure_behavior,
+            },
+            exclude: file.exclude,
+        })
+    }
+}
+
+/// The configuration fragments within an input directory, which are loaded as they're needed.
+///
+/// A fragment applies to everything below the directory containing it, and fragments in deeper
+/// directories take precedence over those in shallower ones. Fragments directly within the input
+/// directory are not considered, as that's where the root configuration commonly lives.
+#[derive(Debug)]
+pub struct Fragments {
+    root: PathBuf,
+    /// Fragments by directory, or `None` for directories without one
+    loaded: Mutex<HashMap<PathBuf, Option<Arc<Fragment>>>>,
+}
+
+impl Fragments {
+    /// Creates an empty set of fragments for the input directory `root`.
+    pub fn new(root: &Path) -> Self {
+        Fragments {
+            root: root.to_path_buf(),
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the fragments applying to `path`, from the outermost directory inwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any applicable fragment can't be read or parsed.
+    pub fn applying_to(&self, path: &Path) -> Result<Vec<Arc<Fragment>>> {
+        let Some(relative) = relative_to(&self.root, path) else {
+            return Ok(Vec::new());
+        };
+
+        let mut fragments = Vec::new();
+        let mut dir = self.root.clone();
+        for component in relative.parent().into_iter().flat_map(Path::components) {
+            dir.push(component);
+            if let Some(fragment) = self.load(&dir)? {
+                fragments.push(fragment);
+            }
+        }
+        Ok(fragments)
+    }
+
+    /// Returns the settings overridden for `path` by all fragments applying to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any applicable fragment can't be read or parsed.
+    pub fn settings_for(&self, path: &Path) -> Result<DirectorySettings> {
+        let mut settings = DirectorySettings::default();
+        for fragment in self.applying_to(path)? {
+            settings.merge(&fragment.settings);
+        }
+        Ok(settings)
+    }
+
+    /// Returns whether a walked path is excluded by a fragment applying to it.
+    ///
+    /// Fragments that can't be loaded don't exclude anything; the error is reported once files
+    /// within their directory are processed.
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        self.applying_to(path).is_ok_and(|fragments| {
+            fragments
+                .iter()
+                .any(|fragment| fragment.matcher.matched(path, is_dir).is_ignore())
+        })
+    }
+
+    fn load(&self, dir: &Path) -> Result<Option<Arc<Fragment>>> {
+        let mut loaded = self.loaded.lock().expect("fragment cache lock poisoned");
+        if let Some(fragment) = loaded.get(dir) {
+            return Ok(fragment.clone());
+        }
+
+        let path = dir.join(FRAGMENT_FILE);
+        let fragment = if path.is_file() {
+            Some(Arc::new(Fragment::load(dir, path)?))
+        } else {
+            None
+        };
+        loaded.insert(dir.to_path_buf(), fragment.clone());
+        Ok(fragment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn tree() -> tempfile::TempDir {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for path in [
+            "default.nix",
+            "strings/ascii.nix",
+            "strings/unicode/utf8.nix",
+        ] {
+            let path = temp_dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{ }").unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_settings_merged_hierarchically() {
+        let temp_dir = tree();
+        let root = temp_dir.path();
+        fs::write(root.join(FRAGMENT_FILE), "prefix = \"ignored\"\n").unwrap();
+        fs::write(
+            root.join("strings").join(FRAGMENT_FILE),
+            "prefix = \"strings\"\nfailure_behavior = \"Skip\"\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("strings/unicode").join(FRAGMENT_FILE),
+            "prefix = \"unicode\"\nanchor_prefix = \"unicode-\"\n",
+        )
+        .unwrap();
+        let fragments = Fragments::new(root);
+
+        assert_eq!(
+            fragments.settings_for(&root.join("default.nix")).unwrap(),
+            DirectorySettings::default()
+        );
+        assert_eq!(
+            fragments
+                .settings_for(&root.join("strings/ascii.nix"))
+                .unwrap(),
+            DirectorySettings {
+                prefix: Some("strings".to_string()),
+                failure_behavior: Some(FailureBehavior::Skip),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            fragments
+                .settings_for(&root.join("strings/unicode/utf8.nix"))
+                .unwrap(),
+            DirectorySettings {
+                prefix: Some("unicode".to_string()),
+                anchor_prefix: Some("unicode-".to_string()),
+                failure_behavior: Some(FailureBehavior::Skip),
+            }
+        );
+    }
+
+    #[test]
+    fn test_exclude_relative_to_fragment() {
+        let temp_dir = tree();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("strings").join(FRAGMENT_FILE),
+            "exclude = [\"/unicode/\", \"default.nix\"]\n",
+        )
+        .unwrap();
+        let fragments = Fragments::new(root);
+
+        assert!(fragments.is_excluded(&root.join("strings/unicode"), true));
+        assert!(!fragments.is_excluded(&root.join("strings/ascii.nix"), false));
+        assert!(!fragments.is_excluded(&root.join("strings"), true));
+        assert!(!fragments.is_excluded(&root.join("default.nix"), false));
+    }
+
+    #[test]
+    fn test_invalid_fragment() {
+        let temp_dir = tree();
+        let root = temp_dir.path();
+        fs::write(root.join("strings").join(FRAGMENT_FILE), "prefix = [").unwrap();
+        let fragments = Fragments::new(root);
+
+        assert!(
+            fragments
+                .settings_for(&root.join("strings/ascii.nix"))
+                .is_err()
+        );
+        assert!(fragments.settings_for(&root.join("default.nix")).is_ok());
+    }
+
+    #[test]
+    fn test_description_override_rejected() {
+        let temp_dir = tree();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("strings").join(FRAGMENT_FILE),
+            "description = \"none\"\n",
+        )
+        .unwrap();
+        let fragments = Fragments::new(root);
+
+        let error = fragments
+            .settings_for(&root.join("strings/ascii.nix"))
+            .unwrap_err();
+        assert!(error.to_string().contains("second line"), "{error:#}");
+    }
+}
@@ -0,0 +1,138 @@
+//! Minimal, dependency-free inspection of local git repositories.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+/// Finds the root of the git repository containing `start`, if any.
+///
+/// The root is the closest ancestor (including `start` itself) containing a `.git` entry.
+pub fn find_root(start: &Path) -> Option<PathBuf> {
+    let start = std::fs::canonicalize(start).ok()?;
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Resolves the git directory for a repository root.
+///
+/// Worktrees and submodules use a `.git` file pointing at the real git directory.
+fn git_dir(root: &Path) -> Result<PathBuf> {
+    let dot_git = root.join(".git");
+    if dot_git.is_dir() {
+        return Ok(dot_git);
+    }
+
+    let content = std::fs::read_to_string(&dot_git)
+        .with_context(|| format!("Failed to read {}", dot_git.display()))?;
+    let git_dir = content
+        .trim()
+        .strip_prefix("gitdir:")
+        .with_context(|| format!("Unrecognized .git file format in {}", dot_git.display()))?;
+    Ok(root.join(git_dir.trim()))
+}
+
+/// Reads the commit currently checked out (HEAD) in the repository at `root`.
+///
+/// Only the on-disk format is inspected; the `git` executable is not required.
+pub fn head_revision(root: &Path) -> Result<String> {
+    let git_dir = git_dir(root)?;
+    let head_path = git_dir.join("HEAD");
+    let head = std::fs::read_to_string(&head_path)
+        .with_context(|| format!("Failed to read {}", head_path.display()))?;
+    let head = head.trim();
+
+    let Some(reference) = head.strip_prefix("ref:").map(str::trim) else {
+        return Ok(head.to_string());
+    };
+
+    // Worktrees keep their own HEAD but share refs with the common git directory
+    let common_dir = match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => git_dir.join(common.trim()),
+        Err(_) => git_dir.clone(),
+    };
+
+    for dir in [&git_dir, &common_dir] {
+        if let Ok(revision) = std::fs::read_to_string(dir.join(reference)) {
+            return Ok(revision.trim().to_string());
+        }
+    }
+
+    if let Ok(packed) = std::fs::read_to_string(common_dir.join("packed-refs")) {
+        let revision = packed
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+            .filter_map(|line| line.split_once(' '))
+            .find(|(_, name)| *name == reference)
+            .map(|(revision, _)| revision.to_string());
+        if let Some(revision) = revision {
+            return Ok(revision);
+        }
+    }
+
+    bail!("Unable to resolve git reference {}", reference)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_head_revision_detached() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        fs::write(temp_dir.path().join(".git/HEAD"), "abc123\n").unwrap();
+
+        assert_eq!(head_revision(temp_dir.path()).unwrap(), "abc123");
+    }
+
+    #[test]
+    fn test_head_revision_loose_ref() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let git_dir = temp_dir.path().join(".git");
+        fs::create_dir_all(git_dir.join("refs/heads")).unwrap();
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(git_dir.join("refs/heads/main"), "def456\n").unwrap();
+
+        assert_eq!(head_revision(temp_dir.path()).unwrap(), "def456");
+    }
+
+    #[test]
+    fn test_head_revision_packed_ref() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let git_dir = temp_dir.path().join(".git");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(
+            git_dir.join("packed-refs"),
+            "# pack-refs with: peeled\n0123abc refs/heads/other\n789fed refs/heads/main\n",
+        )
+        .unwrap();
+
+        assert_eq!(head_revision(temp_dir.path()).unwrap(), "789fed");
+    }
+
+    #[test]
+    fn test_head_revision_unresolvable() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        fs::write(temp_dir.path().join(".git/HEAD"), "ref: refs/heads/gone\n").unwrap();
+
+        assert!(head_revision(temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_find_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let nested = temp_dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir(temp_dir.path().join(".git")).unwrap();
+
+        assert_eq!(
+            find_root(&nested),
+            Some(fs::canonicalize(temp_dir.path()).unwrap())
+        );
+    }
+}
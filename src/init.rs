@@ -0,0 +1,271 @@
+//! Scaffolding of configuration files for new repositories.
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use ignore::Walk;
+use log::warn;
+
+use crate::mapping::relative_to;
+
+/// Top-level directories of an input directory that usually shouldn't be documented.
+const UNDOCUMENTED_DIRS: &[&str] = &["tests", "test", "examples", "fixtures"];
+
+/// Directories that commonly hold a repository's documentation.
+const DOCS_DIRS: &[&str] = &["docs", "doc"];
+
+/// What was found when inspecting an input directory and its surroundings.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Survey {
+    /// Number of Nix files within the input directory
+    pub nix_files: usize,
+    /// Whether the input directory has a `default.nix` entry point
+    pub has_default_nix: bool,
+    /// Existing documentation directory next to the configuration file
+    pub docs_dir: Option<PathBuf>,
+    /// Source directory of an mdBook next to the configuration file
+    pub book_src: Option<PathBuf>,
+    /// Top-level directories of the input directory that usually shouldn't be documented
+    pub undocumented_dirs: Vec<String>,
+}
+
+impl Survey {
+    /// Inspects an input directory and the directory that its configuration is written to.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_dir` - The directory containing the Nix library
+    /// * `config_dir` - The directory that the configuration file is written to
+    pub fn inspect(input_dir: &Path, config_dir: &Path) -> Self {
+        let mut survey = Survey {
+            has_default_nix: input_dir.join("default.nix").is_file(),
+            ..Default::default()
+        };
+
+        for entry in Walk::new(input_dir).filter_map(|entry| entry.ok()) {
+            if entry.path().extension().is_some_and(|ext| ext == "nix") {
+                survey.nix_files += 1;
+            }
+        }
+
+        survey.undocumented_dirs = UNDOCUMENTED_DIRS
+            .iter()
+            .filter(|dir| input_dir.join(dir).is_dir())
+            .map(|dir| dir.to_string())
+            .collect();
+
+        survey.docs_dir = DOCS_DIRS
+            .iter()
+            .map(|dir| config_dir.join(dir))
+            .find(|dir| dir.is_dir());
+
+        let book_dirs = std::iter::once(config_dir.to_path_buf()).chain(survey.docs_dir.clone());
+        survey.book_src = book_dirs
+            .map(|dir| dir.join("book.toml"))
+            .find(|path| path.is_file())
+            .map(|path| book_src(&path));
+
+        survey
+    }
+
+    /// Renders a commented configuration file suited to what was found.
+    ///
+    /// Paths in the configuration are relative to `config_dir`, so that running `autonixdoc`
+    /// without any arguments generates the documentation.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_dir` - The directory containing the Nix library, as given by the user
+    /// * `config_dir` - The directory that the configuration file is written to
+    pub fn scaffold(&self, input_dir: &Path, config_dir: &Path) -> String {
+        let name = input_dir
+            .canonicalize()
+            .ok()
+            .and_then(|dir| {
+                dir.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "lib".to_string());
+        let prefix = if self.has_default_nix {
+            name.replace(
+                |c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_',
+                "",
+            )
+        } else {
+            String::new()
+        };
+        let output_dir = match (&self.book_src, &self.docs_dir) {
+            (Some(src), _) => src.join(&name),
+            (None, Some(docs)) => docs.join(&name),
+            (None, None) => config_dir.join("docs").join(&name),
+        };
+        let relative = |path: &Path| match relative_to(config_dir, path) {
+            Some(relative) if relative.as_os_str().is_empty() => PathBuf::from("."),
+            Some(relative) => relative,
+            None => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+        };
+
+        let mut config = String::new();
+        config.push_str("# Configuration for autonixdoc, generated by `autonixdoc init`.\n#\n");
+        let _ = writeln!(
+            config,
+            "# {} contains {} Nix file(s){}.",
+            input_dir.display(),
+            self.nix_files,
+            if self.has_default_nix {
+                " with a default.nix entry point"
+            } else {
+                ""
+            }
+        );
+        if let Some(src) = &self.book_src {
+            let _ = writeln!(
+                config,
+                "# An mdBook was found, so documentation is best generated within its sources in {}.",
+                src.display()
+            );
+        } else if let Some(docs) = &self.docs_dir {
+            let _ = writeln!(
+                config,
+                "# Documentation is best generated within the existing {} directory.",
+                docs.display()
+            );
+        }
+        config.push_str(
+            "# Files are mapped automatically, mirroring the input directory. Generate by running\n\
+             # `autonixdoc` in this directory, or anywhere below it within the repository.\n\n",
+        );
+
+        let _ = writeln!(
+            config,
+            "# The directory containing the Nix library, relative to this file\n\
+             input_dir = {}\n\n\
+             # The directory where generated documentation is stored, relative to this file\n\
+             output_dir = {}\n",
+            toml::Value::from(relative(input_dir).to_string_lossy().into_owned()),
+            toml::Value::from(relative(&output_dir).to_string_lossy().into_owned())
+        );
+
+        config.push_str(
+            "# Files and directories to ignore, relative to the input directory or this file\n\
+             ignore_paths = []\n\n",
+        );
+        if prefix.is_empty() {
+            config.push_str(
+                "# Prefix for generated identifiers, such as `lib` for `lib.strings.concat`\n\
+                 # prefix = \"lib\"\n\n",
+            );
+        } else {
+            let _ = writeln!(
+                config,
+                "# Prefix for generated identifiers, such as `{0}.strings.concat`\n\
+                 prefix = \"{0}\"\n",
+                prefix
+            );
+        }
+        config.push_str(
+            "# Prefix for anchor links in the generated documentation\n\
+             # anchor_prefix = \"\"\n\n\
+             # How failures to document individual files are handled: Abort, Log or Skip\n\
+             failure_behavior = \"Log\"\n\n\
+             # Gitignore-style patterns of files and directories to leave out\n",
+        );
+        let exclude: Vec<String> = self
+            .undocumented_dirs
+            .iter()
+            .map(|dir| format!("\"/{}/\"", dir))
+            .collect();
+        let _ = writeln!(config, "exclude = [{}]", exclude.join(", "));
+
+        config
+    }
+}
+
+/// Returns the source directory of the mdBook configured at `book_toml`.
+fn book_src(book_toml: &Path) -> PathBuf {
+    let root = book_toml.parent().unwrap_or(Path::new("."));
+    let src = std::fs::read_to_string(book_toml)
+        .ok()
+        .and_then(|content| match content.parse::<toml::Table>() {
+            Ok(table) => Some(table),
+            Err(e) => {
+                warn!("Failed to parse {}: {}", book_toml.display(), e);
+                None
+            }
+        })
+        .and_then(|table| {
+            table
+                .get("book")
+                .and_then(|book| book.get("src"))
+                .and_then(|src| src.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "src".to_string());
+    root.join(src)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::mapping::AutoMappingConfig;
+
+    #[test]
+    fn test_inspect() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let input_dir = root.join("lib");
+        for path in [
+            "default.nix",
+            "strings.nix",
+            "tests/strings.nix",
+            "README.md",
+        ] {
+            let path = input_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{ }").unwrap();
+        }
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/book.toml"), "[book]\nsrc = \"pages\"\n").unwrap();
+
+        let survey = Survey::inspect(&input_dir, root);
+
+        assert_eq!(
+            survey,
+            Survey {
+                nix_files: 3,
+                has_default_nix: true,
+                docs_dir: Some(root.join("docs")),
+                book_src: Some(root.join("docs/pages")),
+                undocumented_dirs: vec!["tests".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_scaffold() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_dir = temp_dir.path().join("lib");
+        fs::create_dir_all(&input_dir).unwrap();
+        let survey = Survey {
+            nix_files: 3,
+            has_default_nix: true,
+            book_src: Some(temp_dir.path().join("docs/src")),
+            undocumented_dirs: vec!["tests".to_string()],
+            ..Default::default()
+        };
+
+        let scaffold = survey.scaffold(&input_dir, temp_dir.path());
+
+        assert!(scaffold.contains("input_dir = \"lib\""));
+        assert!(scaffold.contains("output_dir = \"docs/src/lib\""));
+        assert!(scaffold.contains("prefix = \"lib\""));
+        assert!(scaffold.contains("exclude = [\"/tests/\"]"));
+        let config: AutoMappingConfig = crate::config::parse(Path::new("init"), &scaffold).unwrap();
+        assert_eq!(config.prefix.as_deref(), Some("lib"));
+    }
+}
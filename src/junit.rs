@@ -0,0 +1,230 @@
+//! JUnit XML rendering of per-file generation results.
+
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use clap::ValueEnum;
+
+use crate::{
+    mapping::relative_to,
+    report::{FileReport, FileStatus, RunReport},
+};
+
+/// Name of the suite containing files directly within the input directory.
+const ROOT_SUITE: &str = ".";
+
+/// Renders every processed source file of a run as a JUnit test case.
+///
+/// Test cases are grouped into one suite per top-level directory of the input directory. Files
+/// skipped by path identification aren't source files and are omitted.
+///
+/// When a run has several sources, suite and test case names are additionally prefixed with the
+/// name of the source that contains them.
+///
+/// # Arguments
+///
+/// * `report` - Outcomes of the run
+/// * `sources` - The name and input directory of every source of the run. Test case names are
+///   relative to the input directory.
+pub fn junit(report: &RunReport, sources: &[(&Path, &Path)]) -> String {
+    let mut suites: BTreeMap<String, Vec<(String, &FileReport)>> = BTreeMap::new();
+    for file in report.files() {
+        if file.status == FileStatus::SkippedByIdentification {
+            continue;
+        }
+
+        let (root, relative) = sources
+            .iter()
+            .find_map(|(name, dir)| {
+                relative_to(dir, &file.path).map(|relative| (Some(name), relative))
+            })
+            .unwrap_or_else(|| (None, file.path.clone()));
+        let relative = slashed(&relative);
+        let (mut suite, mut name) = match relative.split_once('/') {
+            Some((top, _)) => (top.to_string(), relative.clone()),
+            None => (ROOT_SUITE.to_string(), relative.clone()),
+        };
+        if let Some(root) = root.filter(|name| !name.as_os_str().is_empty()) {
+            let root = slashed(root);
+            name = format!("{}/{}", root, name);
+            suite = match suite.as_str() {
+                ROOT_SUITE => root,
+                _ => format!("{}/{}", root, suite),
+            };
+        }
+        suites.entry(suite).or_default().push((name, file));
+    }
+
+    let summary = report.summary();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"autonixdoc\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        summary.processed,
+        summary.failed,
+        summary.skipped_by_mapping,
+        summary.duration_ms / 1000.0
+    );
+
+    for (suite, cases) in &suites {
+        let count = |status| cases.iter().filter(|(_, f)| f.status == status).count();
+        let time: f64 = cases.iter().map(|(_, f)| f.duration_ms).sum::<f64>() / 1000.0;
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            escape(suite),
+            cases.len(),
+            count(FileStatus::Failed),
+            count(FileStatus::SkippedByMapping),
+            time
+        );
+
+        for (name, file) in cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape(name),
+                escape(suite),
+                file.duration_ms / 1000.0
+            );
+            match file.status {
+                FileStatus::SkippedByMapping => {
+                    xml.push_str(">\n      <skipped message=\"skipped by path mapping\"/>\n");
+                    xml.push_str("    </testcase>\n");
+                }
+                FileStatus::Failed => {
+                    let error = file.error.as_deref().unwrap_or_default();
+                    let kind = file
+                        .error_kind
+                        .and_then(|kind| kind.to_possible_value())
+                        .map(|kind| kind.get_name().to_string())
+                        .unwrap_or_default();
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{}\" type=\"{}\">{}</failure>",
+                        escape(error.lines().next().unwrap_or_default()),
+                        escape(&kind),
+                        escape(error)
+                    );
+                    xml.push_str("    </testcase>\n");
+                }
+                _ => xml.push_str("/>\n"),
+            }
+        }
+
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Formats a path with forward slashes.
+fn slashed(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "/")
+}
+
+/// Escapes text for use in XML attribute values and character data.
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn test_junit() {
+        let mut report = RunReport::default();
+        report.record(FileReport::new(
+            Path::new("/lib/default.nix"),
+            FileStatus::Written,
+            Duration::from_millis(10),
+        ));
+        report.record(FileReport::new(
+            Path::new("/lib/strings/default.nix"),
+            FileStatus::Written,
+            Duration::from_millis(20),
+        ));
+        report.record(FileReport::new(
+            Path::new("/lib/strings/ignored.nix"),
+            FileStatus::SkippedByMapping,
+            Duration::ZERO,
+        ));
+        report.record(FileReport::new(
+            Path::new("/lib/README.md"),
+            FileStatus::SkippedByIdentification,
+            Duration::ZERO,
+        ));
+        report.record(FileReport {
+            error: Some("nixdoc failed: unexpected <token>\n  at line 1".to_string()),
+            error_kind: Some(ErrorKind::NixdocFailed),
+            ..FileReport::new(
+                &PathBuf::from("/lib/lists/broken.nix"),
+                FileStatus::Failed,
+                Duration::from_millis(5),
+            )
+        });
+        report.finish(Duration::from_millis(1500));
+
+        let xml = junit(&report, &[(Path::new(""), Path::new("/lib"))]);
+
+        assert!(xml.contains(
+            "<testsuites name=\"autonixdoc\" tests=\"4\" failures=\"1\" skipped=\"1\" time=\"1.500\">"
+        ));
+        assert!(xml.contains(
+            "<testsuite name=\".\" tests=\"1\" failures=\"0\" skipped=\"0\" time=\"0.010\">"
+        ));
+        assert!(xml.contains(
+            "<testsuite name=\"strings\" tests=\"2\" failures=\"0\" skipped=\"1\" time=\"0.020\">"
+        ));
+        assert!(xml.contains("<testcase name=\"default.nix\" classname=\".\" time=\"0.010\"/>"));
+        assert!(xml.contains("<skipped message=\"skipped by path mapping\"/>"));
+        assert!(xml.contains(
+            "<failure message=\"nixdoc failed: unexpected &lt;token&gt;\" type=\"nixdoc-failed\">\
+             nixdoc failed: unexpected &lt;token&gt;\n  at line 1</failure>"
+        ));
+        assert!(!xml.contains("README.md"));
+    }
+
+    #[test]
+    fn test_junit_multiple_input_dirs() {
+        let mut report = RunReport::default();
+        for path in [
+            "/repo/lib/default.nix",
+            "/repo/modules/lib/default.nix",
+            "/repo/modules/lib/strings/default.nix",
+        ] {
+            report.record(FileReport::new(
+                Path::new(path),
+                FileStatus::Written,
+                Duration::ZERO,
+            ));
+        }
+
+        let xml = junit(
+            &report,
+            &[
+                (Path::new("lib"), Path::new("/repo/lib")),
+                (Path::new("modules/lib"), Path::new("/repo/modules/lib")),
+            ],
+        );
+
+        assert!(xml.contains("<testsuite name=\"lib\" tests=\"1\""));
+        assert!(xml.contains("<testsuite name=\"modules/lib\" tests=\"1\""));
+        assert!(xml.contains("<testsuite name=\"modules/lib/strings\" tests=\"1\""));
+        assert!(xml.contains(
+            "<testcase name=\"modules/lib/strings/default.nix\" \
+             classname=\"modules/lib/strings\""
+        ));
+        assert!(!xml.contains("/repo"));
+    }
+}
@@ -1,3 +1,21 @@
 pub mod cli;
-mod mapping;
-mod nixdoc;
+mod config;
+mod coverage;
+mod diagnostic;
+mod doclint;
+pub mod error;
+mod fragment;
+mod git;
+mod init;
+mod junit;
+mod linkcheck;
+mod manpage;
+pub mod mapping;
+mod markdown;
+pub mod nixdoc;
+mod nixsource;
+mod report;
+mod search;
+mod sourcelink;
+mod walk;
+mod xref;
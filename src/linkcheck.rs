@@ -0,0 +1,323 @@
+//! Checks for broken links and colliding anchors in generated documentation.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use regex::Regex;
+
+use crate::{
+    diagnostic::{Diagnostic, Severity},
+    markdown::{is_code_fence, parse_heading},
+};
+
+static LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\[[^\]]*\]\(\s*<?([^)\s>]+)>?(?:\s+"[^"]*")?\s*\)"#).expect("valid regex")
+});
+
+static HTML_ANCHOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<a\s+[^>]*(?:id|name)\s*=\s*"([^"]+)""#).expect("valid regex"));
+
+static URL_SCHEME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]*:").expect("valid regex"));
+
+/// An anchor defined within a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Anchor {
+    id: String,
+    line: usize,
+    /// Whether the anchor was given explicitly rather than derived from heading text
+    explicit: bool,
+}
+
+/// A link found within a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Link {
+    target: String,
+    line: usize,
+}
+
+/// Anchors and links of a single markdown page.
+#[derive(Debug, Default)]
+struct Page {
+    anchors: Vec<Anchor>,
+    links: Vec<Link>,
+}
+
+/// Derives the implicit anchor of a heading the same way common markdown renderers do.
+fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Removes inline code spans so that their contents aren't mistaken for links.
+fn strip_code_spans(line: &str) -> String {
+    line.split('`')
+        .enumerate()
+        .filter(|(index, _)| index % 2 == 0)
+        .map(|(_, part)| part)
+        .collect()
+}
+
+impl Page {
+    fn parse(content: &str) -> Self {
+        let mut page = Page::default();
+        let mut in_fence = false;
+
+        for (index, line) in content.lines().enumerate() {
+            let line_number = index + 1;
+            if is_code_fence(line) {
+                in_fence = !in_fence;
+                continue;
+            }
+            if in_fence {
+                continue;
+            }
+
+            if let Some(heading) = parse_heading(line) {
+                page.anchors.push(match heading.anchor {
+                    Some(anchor) => Anchor {
+                        id: anchor.to_string(),
+                        line: line_number,
+                        explicit: true,
+                    },
+                    None => Anchor {
+                        id: slugify(&strip_code_spans(heading.text)),
+                        line: line_number,
+                        explicit: false,
+                    },
+                });
+            }
+
+            for capture in HTML_ANCHOR.captures_iter(line) {
+                page.anchors.push(Anchor {
+                    id: capture[1].to_string(),
+                    line: line_number,
+                    explicit: true,
+                });
+            }
+
+            for capture in LINK.captures_iter(&strip_code_spans(line)) {
+                page.links.push(Link {
+                    target: capture[1].to_string(),
+                    line: line_number,
+                });
+            }
+        }
+
+        page
+    }
+
+    fn has_anchor(&self, id: &str) -> bool {
+        self.anchors.iter().any(|a| a.id == id)
+    }
+}
+
+/// Checks all markdown files within a directory for broken links and duplicate anchors.
+///
+/// The following problems are reported as errors:
+///
+/// - `duplicate-anchor`: an explicit anchor is defined more than once across all pages
+/// - `missing-file`: a relative link points at a file that doesn't exist
+/// - `missing-anchor`: a link fragment doesn't match any anchor of the linked page
+///
+/// Links with a URL scheme (e.g. `https:`) are not checked.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be walked or a markdown file cannot be read.
+pub fn check(dir: &Path) -> Result<Vec<Diagnostic>> {
+    let mut pages = HashMap::new();
+    for entry in WalkBuilder::new(dir).standard_filters(false).build() {
+        let path = entry
+            .with_context(|| "Failed to list directory")?
+            .into_path();
+        if path.extension().is_some_and(|ext| ext == "md") && path.is_file() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            pages.insert(normalize(&path), Page::parse(&content));
+        }
+    }
+
+    let mut paths: Vec<&PathBuf> = pages.keys().collect();
+    paths.sort();
+
+    let mut diagnostics = Vec::new();
+    let mut defined: HashMap<&str, (&Path, usize)> = HashMap::new();
+
+    for path in &paths {
+        for anchor in pages[*path].anchors.iter().filter(|a| a.explicit) {
+            match defined.get(anchor.id.as_str()) {
+                Some((first_path, first_line)) => diagnostics.push(Diagnostic {
+                    rule: "duplicate-anchor".to_string(),
+                    severity: Severity::Error,
+                    path: path.to_path_buf(),
+                    line: Some(anchor.line),
+                    message: format!(
+                        "anchor `{}` is already defined at {}:{}",
+                        anchor.id,
+                        first_path.display(),
+                        first_line
+                    ),
+                }),
+                None => {
+                    defined.insert(&anchor.id, (path, anchor.line));
+                }
+            }
+        }
+    }
+
+    for path in &paths {
+        for link in &pages[*path].links {
+            if let Some(diagnostic) = check_link(path, link, &pages) {
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+fn check_link(path: &Path, link: &Link, pages: &HashMap<PathBuf, Page>) -> Option<Diagnostic> {
+    if URL_SCHEME.is_match(&link.target) || link.target.starts_with("//") {
+        return None;
+    }
+
+    let (file, fragment) = match link.target.split_once('#') {
+        Some((file, fragment)) => (file, Some(fragment)),
+        None => (link.target.as_str(), None),
+    };
+
+    let target = if file.is_empty() {
+        path.to_path_buf()
+    } else {
+        path.parent().unwrap_or(Path::new("")).join(file)
+    };
+
+    let diagnostic = |rule: &str, message: String| Diagnostic {
+        rule: rule.to_string(),
+        severity: Severity::Error,
+        path: path.to_path_buf(),
+        line: Some(link.line),
+        message,
+    };
+
+    if !target.exists() {
+        return Some(diagnostic(
+            "missing-file",
+            format!("link target `{}` does not exist", file),
+        ));
+    }
+
+    let fragment = fragment.filter(|f| !f.is_empty())?;
+    // Only markdown pages within the checked directory have known anchors
+    let target_page = pages.get(&normalize(&target))?;
+
+    if target_page.has_anchor(fragment) {
+        None
+    } else {
+        Some(diagnostic(
+            "missing-anchor",
+            format!(
+                "anchor `{}` is not defined in {}",
+                fragment,
+                target.display()
+            ),
+        ))
+    }
+}
+
+/// Lexically resolves `.` and `..` components so that paths can be compared.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(
+            slugify("lib.strings usage_example"),
+            "libstrings-usage_example"
+        );
+    }
+
+    #[test]
+    fn test_page_parse() {
+        let page = Page::parse(
+            "# Title {#title}\n\n## Plain heading\n\n<a id=\"html\"></a>\n\
+             See [x](other.md#a) and `[not](a-link.md)`.\n```\n[nor](this.md)\n```\n",
+        );
+
+        assert!(page.has_anchor("title"));
+        assert!(page.has_anchor("plain-heading"));
+        assert!(page.has_anchor("html"));
+        assert_eq!(
+            page.links,
+            vec![Link {
+                target: "other.md#a".to_string(),
+                line: 6,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_check_reports_problems() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::create_dir(dir.join("sub")).unwrap();
+        fs::write(
+            dir.join("a.md"),
+            "# A {#shared}\n\n[ok](sub/b.md#b) [same page](#shared) [web](https://example.com)\n\
+             [gone](missing.md) [bad anchor](sub/b.md#nope)\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("sub/b.md"),
+            "# B {#b}\n\n## Again {#shared}\n\n[up](../a.md)\n",
+        )
+        .unwrap();
+
+        let diagnostics = check(dir).unwrap();
+        let rules: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.rule.as_str(), d.line))
+            .collect();
+
+        assert_eq!(
+            rules,
+            vec![
+                ("duplicate-anchor", Some(3)),
+                ("missing-file", Some(4)),
+                ("missing-anchor", Some(4)),
+            ]
+        );
+        assert!(diagnostics[0].path.ends_with("sub/b.md"));
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+    }
+}
@@ -1,7 +1,14 @@
-use anyhow::Result;
+use std::process::ExitCode;
+
 use autonixdoc::cli::Driver;
 use clap::Parser;
 
-fn main() -> Result<()> {
-    Driver::parse().run()
+fn main() -> ExitCode {
+    match Driver::parse().run() {
+        Ok(outcome) => outcome.exit_code(),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
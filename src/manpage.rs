@@ -0,0 +1,356 @@
+//! Conversion of generated documentation into roff man pages.
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::markdown::{DocEntry, DocPage, is_code_fence, parse_heading};
+
+/// The man page section used for all generated pages (library functions).
+const SECTION: &str = "3";
+
+/// Writes section 3 man pages for generated documentation.
+pub struct ManPages<'a> {
+    /// Directory where man pages will be written
+    dir: &'a Path,
+    /// Whether an additional page should be written for each documented function
+    per_function: bool,
+}
+
+impl<'a> ManPages<'a> {
+    /// Creates a new ManPages writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory where man pages will be written
+    /// * `per_function` - Whether to write one page per function in addition to the category page
+    pub fn new(dir: &'a Path, per_function: bool) -> Self {
+        ManPages { dir, per_function }
+    }
+
+    /// Writes the man page(s) for a single documented category.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Fully-qualified name of the category (e.g. `lib.strings`)
+    /// * `page` - The parsed documentation for the category
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output directory or any man page cannot be written.
+    pub fn write(&self, name: &str, page: &DocPage) -> Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(self.dir).with_context(|| {
+            format!(
+                "Failed to create man page directory: {}",
+                self.dir.display()
+            )
+        })?;
+
+        let mut written = vec![self.write_page(name, &render_category(name, page))?];
+        if self.per_function {
+            for entry in &page.entries {
+                written.push(self.write_page(&entry.name, &render_function(name, entry))?);
+            }
+        }
+
+        Ok(written)
+    }
+
+    fn write_page(&self, name: &str, content: &str) -> Result<PathBuf> {
+        let path = self.dir.join(format!("{}.{}", name, SECTION));
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write man page: {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Renders the man page for an entire category.
+fn render_category(name: &str, page: &DocPage) -> String {
+    let mut out = header(name);
+    section_name(&mut out, name, &page.title);
+
+    if !page.entries.is_empty() {
+        out.push_str(".SH SYNOPSIS\n.nf\n");
+        for entry in &page.entries {
+            synopsis_line(&mut out, entry);
+        }
+        out.push_str(".fi\n");
+    }
+
+    out.push_str(".SH DESCRIPTION\n");
+    render_markdown(&mut out, &page.description);
+    for entry in &page.entries {
+        let _ = writeln!(out, ".SS {}", escape(&entry.name));
+        render_entry_body(&mut out, entry);
+    }
+
+    if page.entries.iter().any(|e| !e.examples().is_empty()) {
+        out.push_str(".SH EXAMPLES\n");
+        for entry in page.entries.iter().filter(|e| !e.examples().is_empty()) {
+            let _ = writeln!(out, ".SS {}", escape(&entry.name));
+            render_examples(&mut out, entry);
+        }
+    }
+
+    out
+}
+
+/// Renders the man page for a single function.
+fn render_function(category: &str, entry: &DocEntry) -> String {
+    let mut out = header(&entry.name);
+    section_name(&mut out, &entry.name, &entry.summary());
+
+    out.push_str(".SH SYNOPSIS\n.nf\n");
+    synopsis_line(&mut out, entry);
+    out.push_str(".fi\n");
+
+    out.push_str(".SH DESCRIPTION\n");
+    render_entry_body(&mut out, entry);
+
+    if !entry.examples().is_empty() {
+        out.push_str(".SH EXAMPLES\n");
+        render_examples(&mut out, entry);
+    }
+
+    let _ = writeln!(out, ".SH SEE ALSO\n.BR {} ({})", escape(category), SECTION);
+    out
+}
+
+fn header(name: &str) -> String {
+    format!(
+        ".TH \"{}\" \"{}\" \"\" \"autonixdoc\" \"Nix Library Functions\"\n",
+        escape(name),
+        SECTION
+    )
+}
+
+fn section_name(out: &mut String, name: &str, summary: &str) {
+    let summary = summary.trim();
+    if summary.is_empty() {
+        let _ = writeln!(out, ".SH NAME\n{}", escape(name));
+    } else {
+        let _ = writeln!(out, ".SH NAME\n{} \\- {}", escape(name), escape(summary));
+    }
+}
+
+fn synopsis_line(out: &mut String, entry: &DocEntry) {
+    match entry.type_signature() {
+        Some(signature) => {
+            let signature = signature
+                .lines()
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join(" ");
+            // Type blocks conventionally repeat the function name (`name :: type`)
+            let signature = signature
+                .split_once("::")
+                .map(|(_, ty)| ty.trim())
+                .unwrap_or(&signature);
+            let _ = writeln!(
+                out,
+                "\\fB{}\\fR :: {}",
+                escape(&entry.name),
+                escape(signature)
+            );
+        }
+        None => {
+            let _ = writeln!(out, "\\fB{}\\fR", escape(&entry.name));
+        }
+    }
+}
+
+/// Renders the description of an entry along with all sections except type and examples.
+fn render_entry_body(out: &mut String, entry: &DocEntry) {
+    render_markdown(out, &entry.description);
+    for section in &entry.sections {
+        let heading = section.heading.to_lowercase();
+        if heading.starts_with("type") || heading.starts_with("example") {
+            continue;
+        }
+        let _ = writeln!(out, ".PP\n.B {}", escape(&section.heading));
+        render_markdown(out, &section.lines);
+    }
+}
+
+fn render_examples(out: &mut String, entry: &DocEntry) {
+    for example in entry.examples() {
+        out.push_str(".PP\n.RS 4\n.nf\n");
+        for line in example.lines() {
+            let _ = writeln!(out, "{}", escape_line(line));
+        }
+        out.push_str(".fi\n.RE\n");
+    }
+}
+
+/// Converts a small subset of markdown (paragraphs, lists, code blocks) into roff.
+fn render_markdown(out: &mut String, lines: &[String]) {
+    let mut in_fence = false;
+    let mut paragraph_open = false;
+
+    for line in lines {
+        if is_code_fence(line) {
+            if in_fence {
+                out.push_str(".fi\n.RE\n");
+            } else {
+                out.push_str(".PP\n.RS 4\n.nf\n");
+            }
+            in_fence = !in_fence;
+            paragraph_open = false;
+            continue;
+        }
+
+        if in_fence {
+            let _ = writeln!(out, "{}", escape_line(line));
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(":::") {
+            paragraph_open = false;
+        } else if let Some(heading) = parse_heading(trimmed) {
+            let _ = writeln!(out, ".PP\n.B {}", escape(&inline(heading.text)));
+            paragraph_open = false;
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            let _ = writeln!(out, ".IP \\(bu 2\n{}", escape_line(&inline(item)));
+            paragraph_open = true;
+        } else {
+            if !paragraph_open {
+                out.push_str(".PP\n");
+                paragraph_open = true;
+            }
+            let _ = writeln!(out, "{}", escape_line(&inline(trimmed)));
+        }
+    }
+
+    if in_fence {
+        out.push_str(".fi\n.RE\n");
+    }
+}
+
+/// Converts inline code spans to bold text and strips remaining emphasis markers.
+fn inline(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_code = false;
+
+    for c in text.chars() {
+        match c {
+            '`' => {
+                result.push_str(if in_code { "\u{1}R" } else { "\u{1}B" });
+                in_code = !in_code;
+            }
+            '*' if !in_code => {}
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Escapes roff control characters within text, translating inline font markers.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e")
+        .replace('-', "\\-")
+        .replace('\u{1}', "\\f")
+}
+
+/// Escapes a full line of text, guarding against lines that would be read as requests.
+fn escape_line(line: &str) -> String {
+    let escaped = escape(line);
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"# String manipulation {#sec-functions-library-strings}
+Functions for working with strings.
+
+## `lib.strings.concatStrings` {#function-library-lib.strings.concatStrings}
+
+Concatenate a list of `strings`.
+
+### Inputs
+
+- `list`: the strings
+
+### Type
+
+```
+concatStrings :: [string] -> string
+```
+
+### Examples
+:::{.example}
+```nix
+concatStrings ["foo" "bar"]
+```
+:::
+"#;
+
+    #[test]
+    fn test_render_category_sections() {
+        let page = DocPage::parse(PAGE);
+        let roff = render_category("lib.strings", &page);
+
+        assert!(roff.starts_with(".TH \"lib.strings\" \"3\""));
+        assert!(roff.contains(".SH NAME\nlib.strings \\- String manipulation\n"));
+        assert!(roff.contains(
+            ".SH SYNOPSIS\n.nf\n\\fBlib.strings.concatStrings\\fR :: [string] \\-> string\n.fi\n"
+        ));
+        assert!(roff.contains(".SH DESCRIPTION\n.PP\nFunctions for working with strings.\n"));
+        assert!(roff.contains("Concatenate a list of \\fBstrings\\fR."));
+        assert!(roff.contains(".IP \\(bu 2\n\\fBlist\\fR: the strings\n"));
+        assert!(roff.contains(".SH EXAMPLES\n.SS lib.strings.concatStrings\n"));
+        assert!(roff.contains("concatStrings [\"foo\" \"bar\"]\n"));
+    }
+
+    #[test]
+    fn test_render_function_page() {
+        let page = DocPage::parse(PAGE);
+        let roff = render_function("lib.strings", &page.entries[0]);
+
+        assert!(
+            roff.contains(
+                ".SH NAME\nlib.strings.concatStrings \\- Concatenate a list of strings.\n"
+            )
+        );
+        assert!(roff.contains(".SH SEE ALSO\n.BR lib.strings (3)\n"));
+        assert!(!roff.contains("Functions for working with strings."));
+    }
+
+    #[test]
+    fn test_escape_line_guards_requests() {
+        assert_eq!(escape_line(".foo"), "\\&.foo");
+        assert_eq!(escape_line("a\\b"), "a\\eb");
+    }
+
+    #[test]
+    fn test_write_per_function() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let page = DocPage::parse(PAGE);
+
+        let written = ManPages::new(temp_dir.path(), true)
+            .write("lib.strings", &page)
+            .unwrap();
+
+        assert_eq!(
+            written,
+            vec![
+                temp_dir.path().join("lib.strings.3"),
+                temp_dir.path().join("lib.strings.concatStrings.3"),
+            ]
+        );
+        assert!(written.iter().all(|p| p.exists()));
+    }
+}
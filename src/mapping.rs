@@ -1,21 +1,50 @@
 //! [Path mapping](PathMapping) abstraction.
 
 use std::{
-    collections::HashSet,
-    path::{Path, PathBuf},
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::{Component, Path, PathBuf},
 };
 
-use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, de::DeserializeOwned};
 
-use crate::cli::{FailureBehavior, LogLevel, MappingType};
+use crate::{
+    cli::{FailureBehavior, LogLevel, MappingType},
+    coverage::CoverageConfig,
+    doclint::LintConfig,
+    error::{Error, ErrorKind, Result},
+    walk::WalkConfig,
+};
 
 /// Baseline configuration that all PathMapping configurations should implement.
 ///
 /// This trait provides optional fields for user-configurable values that can be
 /// set via configuration files, environment variables, or CLI arguments. The
 /// priority order is: CLI arguments > environment variables > configuration file values.
+///
+/// Only the settings of the baseline are required; all others default to being unset, so a
+/// configuration implements just the settings it supports.
 pub trait BaselineConfig {
+    /// Returns the directory containing the Nix library, if configured.
+    fn input_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns the directory where generated documentation is stored, if configured.
+    fn output_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns the path mapping strategy, if configured.
+    fn mapping(&self) -> Option<MappingType> {
+        None
+    }
+
+    /// Returns the regular expression identifying the files to process, if configured.
+    fn regex_pattern(&self) -> Option<String> {
+        None
+    }
+
     /// Returns the failure behavior configured in this configuration, if any.
     fn failure_behavior(&self) -> Option<FailureBehavior>;
 
@@ -27,6 +56,153 @@ pub trait BaselineConfig {
 
     /// Returns the logging level configured in this configuration, if any.
     fn logging_level(&self) -> Option<LogLevel>;
+
+    /// Returns the documentation coverage requirements configured in this configuration.
+    fn coverage(&self) -> CoverageConfig {
+        CoverageConfig::default()
+    }
+
+    /// Returns the doc-comment lint configuration configured in this configuration.
+    fn lint(&self) -> LintConfig {
+        LintConfig::default()
+    }
+
+    /// Returns the number of logged failures after which a run aborts, if any.
+    fn max_failures(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the ratio of failed files above which a run is fatal, if any.
+    fn max_failure_ratio(&self) -> Option<f64> {
+        None
+    }
+
+    /// Returns failure behaviors overriding the baseline behavior for specific kinds of error.
+    fn failure_behaviors(&self) -> BTreeMap<ErrorKind, FailureBehavior> {
+        BTreeMap::new()
+    }
+
+    /// Returns the input roots that should be documented in a single run, with relative input
+    /// directories resolved against the directory containing the configuration file.
+    fn sources(&self) -> Vec<SourceConfig> {
+        Vec::new()
+    }
+
+    /// Records the configuration file that this configuration was loaded from, which configured
+    /// paths may be relative to.
+    fn set_config_file(&mut self, _path: &Path) {}
+
+    /// Returns the configuration file that this configuration was loaded from, if any.
+    fn config_file(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns the names of the profiles configured in this configuration.
+    fn profiles(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Records the name of the profile that was applied to this configuration.
+    fn set_active_profile(&mut self, _name: &str) {}
+
+    /// Returns the name of the profile that was applied to this configuration, if any.
+    fn active_profile(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns gitignore-style patterns selecting the files to process, relative to the input
+    /// directory.
+    fn include(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns gitignore-style patterns of files and directories to leave out, relative to the
+    /// input directory.
+    fn exclude(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns the options controlling how input directories are walked.
+    fn walk(&self) -> WalkConfig {
+        WalkConfig::default()
+    }
+
+    /// Returns the directory where man pages are generated, if configured.
+    fn man_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns whether a man page is generated for each documented function, if configured.
+    fn man_per_function(&self) -> Option<bool> {
+        None
+    }
+
+    /// Returns whether a search index is written, if configured.
+    fn search_index(&self) -> Option<bool> {
+        None
+    }
+
+    /// Returns the base URL of the source repository, if configured.
+    fn repo_url(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns the URL template for "Source" links, if configured.
+    fn source_url_template(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns the revision that "Source" links point at, if configured.
+    fn source_rev(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns whether references in code spans are rewritten into links, if configured.
+    fn cross_references(&self) -> Option<bool> {
+        None
+    }
+
+    /// Returns whether the summary of the run is suppressed, if configured.
+    fn quiet(&self) -> Option<bool> {
+        None
+    }
+
+    /// Returns the file that the JSON run report is written to, if configured.
+    fn report(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns the file that the JUnit XML report is written to, if configured.
+    fn junit(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns the file that the SARIF log is written to, if configured.
+    fn sarif(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns whether GitHub Actions annotations are printed, if configured.
+    fn github_annotations(&self) -> Option<bool> {
+        None
+    }
+}
+
+/// An input root documented alongside others in a single run, configured as a `[[sources]]`
+/// array entry.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SourceConfig {
+    /// The directory containing the Nix library
+    pub input_dir: PathBuf,
+    /// Subdirectory of the output directory where its documentation is stored
+    pub output_subdir: Option<PathBuf>,
+    /// Category that the categories of all of its files are nested under
+    pub category_root: Option<String>,
+    /// Prefix for generated identifiers, overriding the baseline prefix
+    pub prefix: Option<String>,
+    /// Prefix for anchor links, overriding the baseline anchor prefix
+    pub anchor_prefix: Option<String>,
 }
 
 /// Actions that can be performed with a mapped path.
@@ -46,11 +222,23 @@ pub enum PathAction {
 /// Path mapping allows implementation of different strategies for documentation
 /// structure.
 pub trait PathMapping {
-    type Config: Default + DeserializeOwned + BaselineConfig;
+    type Config: Default + DeserializeOwned + BaselineConfig + JsonSchema;
 
     fn resolve(&self, config: &Self::Config, nix_path: &Path) -> Result<PathAction>;
+
+    /// Returns the configured ignored paths that don't exist for this mapping, and so have no
+    /// effect on it.
+    fn ineffective_ignore_paths(&self, _config: &Self::Config) -> BTreeSet<PathBuf> {
+        BTreeSet::new()
+    }
 }
 
+/// Configuration shared by every [PathMapping] constructed by [get_mapping].
+///
+/// All mappings share a single configuration type, so that configuration can be resolved
+/// independently of the directories that a mapping is created for.
+pub type MappingConfig = <AutoMapping<'static> as PathMapping>::Config;
+
 /// Constructs a [PathMapping].
 ///
 /// # Arguments
@@ -62,7 +250,7 @@ pub fn get_mapping<'a>(
     mapping_type: MappingType,
     source_base: &'a Path,
     dest_base: &'a Path,
-) -> impl PathMapping {
+) -> impl PathMapping<Config = MappingConfig> {
     match mapping_type {
         MappingType::Auto => AutoMapping {
             source_base,
@@ -71,6 +259,43 @@ pub fn get_mapping<'a>(
     }
 }
 
+/// Returns `path` relative to `base`, or `None` if `path` isn't within `base`.
+///
+/// Paths are first compared lexically once made absolute, so that differently normalized
+/// spellings such as `./lib` and `lib` match. Failing that, the directories of both paths are
+/// canonicalized so that paths reached through symlinked directories match their targets.
+pub(crate) fn relative_to(base: &Path, path: &Path) -> Option<PathBuf> {
+    let base = std::path::absolute(base).ok()?;
+    let path = std::path::absolute(path).ok()?;
+
+    if let Ok(relative) = normalize(&path).strip_prefix(normalize(&base)) {
+        return Some(relative.to_path_buf());
+    }
+
+    let path = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent.canonicalize().ok()?.join(name),
+        _ => path.canonicalize().ok()?,
+    };
+    path.strip_prefix(base.canonicalize().ok()?)
+        .ok()
+        .map(Path::to_path_buf)
+}
+
+/// Lexically removes `.` components and resolves `..` components of an absolute path.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
 /// Mirrors source file paths to corresponding documentation paths.
 ///
 /// This implementation transforms source paths by preserving the directory
@@ -82,6 +307,25 @@ pub struct AutoMapping<'a> {
     dest_base: &'a Path,
 }
 
+impl AutoMappingConfig {
+    /// Returns the directory containing the configuration file, if this configuration was
+    /// loaded from one.
+    fn config_dir(&self) -> Option<&Path> {
+        self.config_file.as_deref().map(|path| match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        })
+    }
+
+    /// Resolves a configured path against the directory containing the configuration file.
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        match self.config_dir() {
+            Some(config_dir) if path.is_relative() => config_dir.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+}
+
 impl<'a> AutoMapping<'a> {
     /// Creates a new MirrorMapping instance.
     ///
@@ -98,21 +342,132 @@ impl<'a> AutoMapping<'a> {
     }
 }
 
-#[derive(Default, Deserialize)]
+/// Configuration of the automatic mapping, loaded from `autonixdoc.toml`.
+#[derive(Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct AutoMappingConfig {
-    /// Paths to ignore during documentation generation
+    /// Files and directories to ignore during documentation generation
+    ///
+    /// Relative paths may be relative to the input directory or the directory containing the
+    /// configuration file.
+    #[serde(default)]
     pub ignore_paths: HashSet<PathBuf>,
+    /// Configuration file that this configuration was loaded from, if any
+    #[serde(skip)]
+    pub config_file: Option<PathBuf>,
+    /// Profile that was applied to this configuration, if any
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    /// The directory containing the Nix library, relative to the configuration file
+    ///
+    /// Several libraries can instead be documented by configuring `[[sources]]`, which are
+    /// ignored when this is given.
+    pub input_dir: Option<PathBuf>,
+    /// The directory where generated documentation is stored, relative to the configuration file
+    pub output_dir: Option<PathBuf>,
+    /// The path mapping strategy used to generate documentation
+    #[schemars(extend("default" = "auto"))]
+    pub mapping: Option<MappingType>,
+    /// Regular expression identifying the files to process, instead of all `.nix` files
+    pub regex_pattern: Option<String>,
+    /// Gitignore-style patterns selecting the files to process
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Gitignore-style patterns of files and directories to leave out
+    #[serde(default)]
+    pub exclude: Vec<String>,
     /// Failure behavior configuration
+    #[schemars(extend("default" = "Log"))]
     pub failure_behavior: Option<FailureBehavior>,
+    /// Failure behavior overrides for specific kinds of error
+    #[serde(default)]
+    pub failure_behaviors: BTreeMap<ErrorKind, FailureBehavior>,
+    /// Number of logged failures after which a run aborts
+    pub max_failures: Option<usize>,
+    /// Ratio of failed files above which a run is fatal
+    pub max_failure_ratio: Option<f64>,
     /// Prefix for generated identifiers
+    #[schemars(extend("default" = ""))]
     pub prefix: Option<String>,
     /// Prefix for anchor links
+    #[schemars(extend("default" = ""))]
     pub anchor_prefix: Option<String>,
-    /// Logging level configuration as string (info, warn, error)
-    pub logging_level: Option<String>,
+    /// Logging level (error, warn, info, debug or trace)
+    pub logging_level: Option<LogLevel>,
+    /// Documentation coverage requirements
+    #[serde(default)]
+    pub coverage: CoverageConfig,
+    /// Doc-comment lint configuration
+    #[serde(default)]
+    pub lint: LintConfig,
+    /// Input roots documented in a single run
+    #[serde(default)]
+    pub sources: Vec<SourceConfig>,
+    /// Options controlling how input directories are walked
+    #[serde(default)]
+    pub walk: WalkConfig,
+    /// Directory where section 3 man pages are generated, relative to the configuration file
+    pub man_dir: Option<PathBuf>,
+    /// Whether a man page is additionally generated for each documented function
+    #[schemars(extend("default" = false))]
+    pub man_per_function: Option<bool>,
+    /// Whether a search-index.json describing every documented function is written
+    #[schemars(extend("default" = false))]
+    pub search_index: Option<bool>,
+    /// Base URL of the source repository, enabling "Source" links for documented functions
+    pub repo_url: Option<String>,
+    /// URL template for "Source" links, supporting the {repo}, {rev}, {path} and {line}
+    /// placeholders
+    #[schemars(extend("default" = "{repo}/blob/{rev}/{path}#L{line}"))]
+    pub source_url_template: Option<String>,
+    /// Revision that "Source" links point at, by default the commit checked out
+    pub source_rev: Option<String>,
+    /// Whether references to documented functions in code spans are rewritten into links
+    #[schemars(extend("default" = false))]
+    pub cross_references: Option<bool>,
+    /// Whether the summary of the run is suppressed
+    #[schemars(extend("default" = false))]
+    pub quiet: Option<bool>,
+    /// JSON file that the outcome of every file is written to, relative to the configuration file
+    pub report: Option<PathBuf>,
+    /// JUnit XML file that the outcome of every file is written to, relative to the
+    /// configuration file
+    pub junit: Option<PathBuf>,
+    /// SARIF 2.1.0 file that problems are written to, relative to the configuration file
+    pub sarif: Option<PathBuf>,
+    /// Whether problems are printed to stderr as GitHub Actions annotations
+    #[schemars(extend("default" = false))]
+    pub github_annotations: Option<bool>,
+    /// Named profiles, selected with --profile, whose keys override those of this configuration
+    ///
+    /// Tables within a profile are merged with the corresponding tables of this configuration,
+    /// while all other values replace the configured ones.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, serde_json::Map<String, serde_json::Value>>")]
+    pub profile: BTreeMap<String, toml::Table>,
 }
 
 impl BaselineConfig for AutoMappingConfig {
+    fn input_dir(&self) -> Option<PathBuf> {
+        self.input_dir
+            .as_deref()
+            .map(|path| self.resolve_path(path))
+    }
+
+    fn output_dir(&self) -> Option<PathBuf> {
+        self.output_dir
+            .as_deref()
+            .map(|path| self.resolve_path(path))
+    }
+
+    fn mapping(&self) -> Option<MappingType> {
+        self.mapping
+    }
+
+    fn regex_pattern(&self) -> Option<String> {
+        self.regex_pattern.clone()
+    }
+
     fn failure_behavior(&self) -> Option<FailureBehavior> {
         self.failure_behavior
     }
@@ -126,7 +481,153 @@ impl BaselineConfig for AutoMappingConfig {
     }
 
     fn logging_level(&self) -> Option<LogLevel> {
-        self.logging_level.as_ref().and_then(|s| s.parse().ok())
+        self.logging_level
+    }
+
+    fn coverage(&self) -> CoverageConfig {
+        self.coverage.clone()
+    }
+
+    fn lint(&self) -> LintConfig {
+        self.lint.clone()
+    }
+
+    fn max_failures(&self) -> Option<usize> {
+        self.max_failures
+    }
+
+    fn max_failure_ratio(&self) -> Option<f64> {
+        self.max_failure_ratio
+    }
+
+    fn failure_behaviors(&self) -> BTreeMap<ErrorKind, FailureBehavior> {
+        self.failure_behaviors.clone()
+    }
+
+    fn sources(&self) -> Vec<SourceConfig> {
+        let mut sources = self.sources.clone();
+        for source in &mut sources {
+            source.input_dir = self.resolve_path(&source.input_dir);
+        }
+        sources
+    }
+
+    fn set_config_file(&mut self, path: &Path) {
+        self.config_file = Some(path.to_path_buf());
+    }
+
+    fn config_file(&self) -> Option<PathBuf> {
+        self.config_file.clone()
+    }
+
+    fn profiles(&self) -> Vec<String> {
+        self.profile.keys().cloned().collect()
+    }
+
+    fn set_active_profile(&mut self, name: &str) {
+        self.active_profile = Some(name.to_string());
+    }
+
+    fn active_profile(&self) -> Option<String> {
+        self.active_profile.clone()
+    }
+
+    fn include(&self) -> Vec<String> {
+        self.include.clone()
+    }
+
+    fn exclude(&self) -> Vec<String> {
+        self.exclude.clone()
+    }
+
+    fn walk(&self) -> WalkConfig {
+        self.walk.clone()
+    }
+
+    fn man_dir(&self) -> Option<PathBuf> {
+        self.man_dir.as_deref().map(|path| self.resolve_path(path))
+    }
+
+    fn man_per_function(&self) -> Option<bool> {
+        self.man_per_function
+    }
+
+    fn search_index(&self) -> Option<bool> {
+        self.search_index
+    }
+
+    fn repo_url(&self) -> Option<String> {
+        self.repo_url.clone()
+    }
+
+    fn source_url_template(&self) -> Option<String> {
+        self.source_url_template.clone()
+    }
+
+    fn source_rev(&self) -> Option<String> {
+        self.source_rev.clone()
+    }
+
+    fn cross_references(&self) -> Option<bool> {
+        self.cross_references
+    }
+
+    fn quiet(&self) -> Option<bool> {
+        self.quiet
+    }
+
+    fn report(&self) -> Option<PathBuf> {
+        self.report.as_deref().map(|path| self.resolve_path(path))
+    }
+
+    fn junit(&self) -> Option<PathBuf> {
+        self.junit.as_deref().map(|path| self.resolve_path(path))
+    }
+
+    fn sarif(&self) -> Option<PathBuf> {
+        self.sarif.as_deref().map(|path| self.resolve_path(path))
+    }
+
+    fn github_annotations(&self) -> Option<bool> {
+        self.github_annotations
+    }
+}
+
+impl<'a> AutoMapping<'a> {
+    /// Returns the paths that an `ignore_paths` entry could refer to, normalized and absolute.
+    fn ignore_candidates(&self, config: &AutoMappingConfig, entry: &Path) -> Vec<PathBuf> {
+        let mut candidates = Vec::new();
+        if entry.is_relative() {
+            candidates.push(self.source_base.join(entry));
+            if let Some(config_dir) = config.config_dir() {
+                candidates.push(config_dir.join(entry));
+            }
+        } else {
+            candidates.push(entry.to_path_buf());
+        }
+
+        candidates
+            .iter()
+            .filter_map(|candidate| std::path::absolute(candidate).ok())
+            .map(|candidate| normalize(&candidate))
+            .collect()
+    }
+
+    /// Returns whether a source path is, or is within, an ignored path.
+    fn is_ignored(&self, config: &AutoMappingConfig, source_path: &Path) -> bool {
+        if config.ignore_paths.is_empty() {
+            return false;
+        }
+        let Ok(source_path) = std::path::absolute(source_path) else {
+            return false;
+        };
+        let source_path = normalize(&source_path);
+
+        config.ignore_paths.iter().any(|entry| {
+            self.ignore_candidates(config, entry)
+                .iter()
+                .any(|candidate| source_path.starts_with(candidate))
+        })
     }
 }
 
@@ -134,20 +635,27 @@ impl<'a> PathMapping for AutoMapping<'a> {
     type Config = AutoMappingConfig;
 
     fn resolve(&self, config: &Self::Config, source_path: &Path) -> Result<PathAction> {
-        if config.ignore_paths.contains(source_path) {
+        if self.is_ignored(config, source_path) {
             return Ok(PathAction::Skip);
         }
 
+        let mapping_failed = |reason: &str| Error::MappingFailed {
+            path: source_path.to_path_buf(),
+            reason: reason.to_string(),
+        };
+
         let source_dir = source_path
             .parent()
-            .with_context(|| "source path had no parent")?;
-        let relative_path = source_dir.strip_prefix(self.source_base).expect(
-            "Source directory isn't a prefix of source path? Please report this, it's a bug",
-        );
+            .ok_or_else(|| mapping_failed("source path had no parent"))?;
+        let relative_path =
+            relative_to(self.source_base, source_dir).ok_or_else(|| Error::OutsideInputDir {
+                path: source_path.to_path_buf(),
+                input_dir: self.source_base.to_path_buf(),
+            })?;
 
         let source_stem = source_path
             .file_stem()
-            .with_context(|| "source path had no file name")?;
+            .ok_or_else(|| mapping_failed("source path had no file name"))?;
 
         Ok(PathAction::OutputTo(
             self.dest_base
@@ -157,6 +665,19 @@ impl<'a> PathMapping for AutoMapping<'a> {
                 .with_extension("md"),
         ))
     }
+    fn ineffective_ignore_paths(&self, config: &Self::Config) -> BTreeSet<PathBuf> {
+        config
+            .ignore_paths
+            .iter()
+            .filter(|entry| {
+                !self
+                    .ignore_candidates(config, entry)
+                    .iter()
+                    .any(|candidate| candidate.exists())
+            })
+            .cloned()
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -264,8 +785,10 @@ mod tests {
         let mapping = AutoMapping::new(&source_base, &dest_base);
         let result = mapping.resolve(&Default::default(), &source_path);
 
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err().to_string(), "source path had no parent");
+        assert!(matches!(
+            result,
+            Err(Error::MappingFailed { reason, .. }) if reason == "source path had no parent"
+        ));
     }
 
     #[test]
@@ -277,11 +800,10 @@ mod tests {
         let mapping = AutoMapping::new(&source_base, &dest_base);
         let result = mapping.resolve(&Default::default(), &source_path);
 
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err().to_string(),
-            "source path had no file name"
-        );
+        assert!(matches!(
+            result,
+            Err(Error::MappingFailed { reason, .. }) if reason == "source path had no file name"
+        ));
     }
 
     #[test]
@@ -299,29 +821,84 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(
-        expected = "Source directory isn't a prefix of source path? Please report this, it's a bug"
-    )]
-    fn test_auto_mapping_invalid_prefix_panic() {
+    fn test_auto_mapping_outside_source_base_error() {
         let source_path = PathBuf::from("/other/lib/module.nix");
         let source_base = PathBuf::from("/src");
         let dest_base = PathBuf::from("/docs");
 
         let mapping = AutoMapping::new(&source_base, &dest_base);
-        let _ = mapping.resolve(&Default::default(), &source_path);
+        let result = mapping.resolve(&Default::default(), &source_path);
+        assert!(matches!(result, Err(Error::OutsideInputDir { .. })));
     }
 
     #[test]
-    #[should_panic(
-        expected = "Source directory isn't a prefix of source path? Please report this, it's a bug"
-    )]
-    fn test_auto_mapping_relative_invalid_prefix_panic() {
+    fn test_auto_mapping_relative_outside_source_base_error() {
         let source_path = PathBuf::from("other/lib/module.nix");
         let source_base = PathBuf::from("src");
         let dest_base = PathBuf::from("docs");
 
         let mapping = AutoMapping::new(&source_base, &dest_base);
-        let _ = mapping.resolve(&Default::default(), &source_path);
+        let result = mapping.resolve(&Default::default(), &source_path);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "other/lib/module.nix is not within the input directory src"
+        );
+    }
+
+    #[test]
+    fn test_auto_mapping_differently_normalized_paths() {
+        let dest_base = PathBuf::from("docs");
+
+        let source_base = PathBuf::from("./src");
+        let mapping = AutoMapping::new(&source_base, &dest_base);
+        let result = mapping
+            .resolve(&Default::default(), Path::new("src/lib/module.nix"))
+            .unwrap();
+        assert_eq!(
+            result,
+            PathAction::OutputTo(PathBuf::from("docs/lib/module.md"))
+        );
+
+        let source_base = PathBuf::from("/src/lib/..");
+        let mapping = AutoMapping::new(&source_base, &dest_base);
+        let result = mapping
+            .resolve(&Default::default(), Path::new("/src/./lib/module.nix"))
+            .unwrap();
+        assert_eq!(
+            result,
+            PathAction::OutputTo(PathBuf::from("docs/lib/module.md"))
+        );
+    }
+
+    #[test]
+    fn test_auto_mapping_symlinked_source_base() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let target = temp_dir.path().join("target");
+        std::fs::create_dir_all(target.join("lib")).unwrap();
+        std::fs::write(target.join("lib/module.nix"), "{ }").unwrap();
+        let link = temp_dir.path().join("link");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let dest_base = PathBuf::from("/docs");
+
+        // Paths found through the link, mapped relative to its target
+        let mapping = AutoMapping::new(&target, &dest_base);
+        let result = mapping
+            .resolve(&Default::default(), &link.join("lib/module.nix"))
+            .unwrap();
+        assert_eq!(
+            result,
+            PathAction::OutputTo(PathBuf::from("/docs/lib/module.md"))
+        );
+
+        // Paths found in the target, mapped relative to the link
+        let mapping = AutoMapping::new(&link, &dest_base);
+        let result = mapping
+            .resolve(&Default::default(), &target.join("lib/module.nix"))
+            .unwrap();
+        assert_eq!(
+            result,
+            PathAction::OutputTo(PathBuf::from("/docs/lib/module.md"))
+        );
     }
 
     #[test]
@@ -373,7 +950,7 @@ mod tests {
         let dest_base = PathBuf::from("docs");
 
         let mut config = AutoMappingConfig::default();
-        config.ignore_paths.insert(source_path.clone());
+        config.ignore_paths.insert(PathBuf::from("lib/ignored.nix"));
 
         let mapping = AutoMapping::new(&source_base, &dest_base);
         let result = mapping.resolve(&config, &source_path).unwrap();
@@ -381,6 +958,24 @@ mod tests {
         assert_eq!(result, PathAction::Skip);
     }
 
+    #[test]
+    fn test_ignore_paths_not_relative_to_current_directory() {
+        let source_path = PathBuf::from("src/lib/ignored.nix");
+        let source_base = PathBuf::from("src");
+        let dest_base = PathBuf::from("docs");
+
+        let mut config = AutoMappingConfig::default();
+        config.ignore_paths.insert(source_path.clone());
+
+        let mapping = AutoMapping::new(&source_base, &dest_base);
+        let result = mapping.resolve(&config, &source_path).unwrap();
+
+        assert_eq!(
+            result,
+            PathAction::OutputTo(PathBuf::from("docs/lib/ignored.md"))
+        );
+    }
+
     #[test]
     fn test_ignore_paths_not_ignored() {
         let ignored_path = PathBuf::from("/src/lib/ignored.nix");
@@ -420,6 +1015,101 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ignore_paths_relative_to_input_directory() {
+        let source_base = PathBuf::from("/project/./lib");
+        let dest_base = PathBuf::from("/output");
+
+        let mut config = AutoMappingConfig::default();
+        config.ignore_paths.insert(PathBuf::from("strings.nix"));
+        config.ignore_paths.insert(PathBuf::from("tests"));
+
+        let mapping = AutoMapping::new(&source_base, &dest_base);
+
+        for ignored in [
+            "/project/lib/strings.nix",
+            "/project/./lib/strings.nix",
+            "/project/lib/tests/strings.nix",
+            "/project/lib/tests/fixtures/broken.nix",
+        ] {
+            let result = mapping.resolve(&config, Path::new(ignored)).unwrap();
+            assert_eq!(result, PathAction::Skip, "{} should be ignored", ignored);
+        }
+
+        let result = mapping
+            .resolve(&config, Path::new("/project/lib/lists.nix"))
+            .unwrap();
+        assert_eq!(
+            result,
+            PathAction::OutputTo(PathBuf::from("/output/lists.md"))
+        );
+    }
+
+    #[test]
+    fn test_ignore_paths_relative_to_config_directory() {
+        let source_base = PathBuf::from("/project/lib");
+        let dest_base = PathBuf::from("/output");
+
+        let mut config = AutoMappingConfig::default();
+        config.ignore_paths.insert(PathBuf::from("lib/strings.nix"));
+        config.set_config_file(Path::new("/project/autonixdoc.toml"));
+
+        let mapping = AutoMapping::new(&source_base, &dest_base);
+        let result = mapping
+            .resolve(&config, Path::new("/project/lib/strings.nix"))
+            .unwrap();
+
+        assert_eq!(result, PathAction::Skip);
+    }
+
+    #[test]
+    fn test_sources_relative_to_config_directory() {
+        let mut config: AutoMappingConfig = toml::from_str(
+            "ignore_paths = []\n\n[[sources]]\ninput_dir = \"lib\"\n\n[[sources]]\ninput_dir = \"/nix/pkgs\"\n",
+        )
+        .unwrap();
+        config.set_config_file(Path::new("/project/autonixdoc.toml"));
+
+        let input_dirs: Vec<PathBuf> = config
+            .sources()
+            .into_iter()
+            .map(|source| source.input_dir)
+            .collect();
+
+        assert_eq!(
+            input_dirs,
+            vec![PathBuf::from("/project/lib"), PathBuf::from("/nix/pkgs")]
+        );
+    }
+
+    #[test]
+    fn test_paths_relative_to_config_directory() {
+        let mut config: AutoMappingConfig = toml::from_str(
+            "ignore_paths = []\ninput_dir = \"lib\"\noutput_dir = \"/srv/docs\"\nreport = \"build/report.json\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.input_dir(), Some(PathBuf::from("lib")));
+
+        config.set_config_file(Path::new("/project/autonixdoc.toml"));
+
+        assert_eq!(config.input_dir(), Some(PathBuf::from("/project/lib")));
+        assert_eq!(config.output_dir(), Some(PathBuf::from("/srv/docs")));
+        assert_eq!(
+            config.report(),
+            Some(PathBuf::from("/project/build/report.json"))
+        );
+        assert_eq!(config.man_dir(), None);
+    }
+
+    #[test]
+    fn test_baseline_config_minimal() {
+        let config: AutoMappingConfig =
+            toml::from_str("input_dir = \"lib\"\noutput_dir = \"docs\"\n").unwrap();
+
+        assert!(config.ignore_paths.is_empty());
+        assert_eq!(config.input_dir(), Some(PathBuf::from("lib")));
+    }
+
     #[test]
     fn test_baseline_config_default_values() {
         let config = AutoMappingConfig::default();
@@ -430,13 +1120,78 @@ mod tests {
         assert_eq!(config.logging_level(), None);
     }
 
+    #[test]
+    fn test_baseline_config_failure_behaviors() {
+        let config: AutoMappingConfig = toml::from_str(
+            r#"
+            ignore_paths = []
+            failure_behavior = "Log"
+
+            [failure_behaviors]
+            nixdoc-missing = "Abort"
+            mapping-failed = "Skip"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.failure_behaviors(),
+            BTreeMap::from([
+                (ErrorKind::NixdocMissing, FailureBehavior::Abort),
+                (ErrorKind::MappingFailed, FailureBehavior::Skip),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_baseline_config_sources() {
+        let config: AutoMappingConfig = toml::from_str(
+            r#"
+            ignore_paths = []
+
+            [[sources]]
+            input_dir = "lib"
+
+            [[sources]]
+            input_dir = "modules/lib"
+            output_subdir = "modules"
+            category_root = "modules"
+            prefix = "modulesLib"
+            anchor_prefix = "modules-"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.sources(),
+            vec![
+                SourceConfig {
+                    input_dir: PathBuf::from("lib"),
+                    output_subdir: None,
+                    category_root: None,
+                    prefix: None,
+                    anchor_prefix: None,
+                },
+                SourceConfig {
+                    input_dir: PathBuf::from("modules/lib"),
+                    output_subdir: Some(PathBuf::from("modules")),
+                    category_root: Some("modules".to_string()),
+                    prefix: Some("modulesLib".to_string()),
+                    anchor_prefix: Some("modules-".to_string()),
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_baseline_config_with_values() {
-        let mut config = AutoMappingConfig::default();
-        config.failure_behavior = Some(FailureBehavior::Abort);
-        config.prefix = Some("test-prefix".to_string());
-        config.anchor_prefix = Some("test-anchor".to_string());
-        config.logging_level = Some("info".to_string());
+        let config = AutoMappingConfig {
+            failure_behavior: Some(FailureBehavior::Abort),
+            prefix: Some("test-prefix".to_string()),
+            anchor_prefix: Some("test-anchor".to_string()),
+            logging_level: Some(LogLevel(log::LevelFilter::Info)),
+            ..Default::default()
+        };
 
         assert_eq!(config.failure_behavior(), Some(FailureBehavior::Abort));
         assert_eq!(config.prefix(), Some("test-prefix".to_string()));
@@ -460,8 +1215,9 @@ mod tests {
         ];
 
         for (input, expected) in test_cases {
-            let mut config = AutoMappingConfig::default();
-            config.logging_level = Some(input.to_string());
+            let config: AutoMappingConfig =
+                toml::from_str(&format!("ignore_paths = []\nlogging_level = \"{}\"", input))
+                    .unwrap();
 
             assert_eq!(
                 config.logging_level(),
@@ -472,14 +1228,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_baseline_config_invalid_logging_level() {
+        let result: std::result::Result<AutoMappingConfig, _> =
+            toml::from_str("ignore_paths = []\nlogging_level = \"verbose\"");
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_baseline_config_none_logging_level() {
         let config = AutoMappingConfig {
             ignore_paths: HashSet::new(),
+            config_file: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
             failure_behavior: None,
+            failure_behaviors: BTreeMap::new(),
+            max_failures: None,
+            max_failure_ratio: None,
             prefix: None,
             anchor_prefix: None,
             logging_level: None,
+            coverage: CoverageConfig::default(),
+            lint: LintConfig::default(),
+            sources: Vec::new(),
+            walk: WalkConfig::default(),
+            ..Default::default()
         };
 
         assert_eq!(config.logging_level(), None);
@@ -0,0 +1,358 @@
+//! Structured view over the markdown documents generated by nixdoc.
+//!
+//! nixdoc emits a predictable layout: a single level one heading describing the category,
+//! followed by one level two heading per documented function. Each heading carries an
+//! explicit anchor in the `{#anchor}` attribute syntax. Function bodies may contain further
+//! headings (e.g. `Type` or `Examples`) which are treated as [sections](Section).
+
+/// A single generated documentation page.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DocPage {
+    /// Title of the page, taken from the level one heading
+    pub title: String,
+    /// Anchor attached to the page title, if any
+    pub anchor: Option<String>,
+    /// Free-form text preceding the first documented function
+    pub description: Vec<String>,
+    /// Documented functions in the order they appear on the page
+    pub entries: Vec<DocEntry>,
+}
+
+/// A single documented function within a [DocPage].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DocEntry {
+    /// Fully-qualified name of the function (e.g. `lib.strings.concatStrings`)
+    pub name: String,
+    /// Anchor attached to the function heading, if any
+    pub anchor: Option<String>,
+    /// 1-based line of the function heading within the page
+    pub line: usize,
+    /// Text preceding the first section heading of the function
+    pub description: Vec<String>,
+    /// Headed sections of the function documentation
+    pub sections: Vec<Section>,
+}
+
+/// A headed section within a [DocEntry].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Heading text of the section, without markup
+    pub heading: String,
+    /// Raw markdown lines belonging to the section
+    pub lines: Vec<String>,
+}
+
+/// A parsed markdown heading.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Heading<'a> {
+    /// Heading level (number of leading `#` characters)
+    pub level: usize,
+    /// Heading text with any trailing anchor attribute removed
+    pub text: &'a str,
+    /// Explicit anchor attached to the heading, if any
+    pub anchor: Option<&'a str>,
+}
+
+/// Parses a single line as an ATX heading, returning None if it isn't one.
+pub fn parse_heading(line: &str) -> Option<Heading<'_>> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+
+    let rest = rest.trim();
+    if let Some(start) = rest.rfind("{#")
+        && rest.ends_with('}')
+    {
+        let anchor = &rest[start + 2..rest.len() - 1];
+        Some(Heading {
+            level,
+            text: rest[..start].trim_end(),
+            anchor: Some(anchor),
+        })
+    } else {
+        Some(Heading {
+            level,
+            text: rest,
+            anchor: None,
+        })
+    }
+}
+
+/// Returns true if the line opens or closes a fenced code block.
+pub fn is_code_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// Strips a single pair of surrounding backticks from heading text, if present.
+fn strip_code_span(text: &str) -> &str {
+    text.strip_prefix('`')
+        .and_then(|t| t.strip_suffix('`'))
+        .unwrap_or(text)
+}
+
+impl DocPage {
+    /// Parses the markdown produced by a single nixdoc invocation.
+    pub fn parse(content: &str) -> Self {
+        let mut page = DocPage::default();
+        let mut in_fence = false;
+        let mut div_depth = 0usize;
+
+        for (index, line) in content.lines().enumerate() {
+            if is_code_fence(line) {
+                in_fence = !in_fence;
+            } else if !in_fence && line.trim_start().starts_with(":::") {
+                if line.trim() == ":::" {
+                    div_depth = div_depth.saturating_sub(1);
+                } else {
+                    div_depth += 1;
+                }
+            } else if !in_fence
+                && div_depth == 0
+                && let Some(heading) = parse_heading(line)
+            {
+                if heading.level == 1 && page.entries.is_empty() && page.title.is_empty() {
+                    page.title = heading.text.to_string();
+                    page.anchor = heading.anchor.map(str::to_string);
+                    continue;
+                }
+
+                if heading.level == 2 && heading.anchor.is_some() {
+                    page.entries.push(DocEntry {
+                        name: strip_code_span(heading.text).to_string(),
+                        anchor: heading.anchor.map(str::to_string),
+                        line: index + 1,
+                        ..Default::default()
+                    });
+                    continue;
+                }
+
+                if let Some(entry) = page.entries.last_mut() {
+                    entry.sections.push(Section {
+                        heading: heading.text.to_string(),
+                        lines: Vec::new(),
+                    });
+                    continue;
+                }
+            }
+
+            match page.entries.last_mut() {
+                Some(entry) => match entry.sections.last_mut() {
+                    Some(section) => section.lines.push(line.to_string()),
+                    None => entry.description.push(line.to_string()),
+                },
+                None => page.description.push(line.to_string()),
+            }
+        }
+
+        page
+    }
+}
+
+impl DocEntry {
+    /// Returns the first paragraph of the function description as plain text.
+    pub fn summary(&self) -> String {
+        first_paragraph(&self.description)
+    }
+
+    /// Returns the first section whose heading starts with the provided text (case-insensitive).
+    pub fn section(&self, heading: &str) -> Option<&Section> {
+        let heading = heading.to_lowercase();
+        self.sections
+            .iter()
+            .find(|s| s.heading.to_lowercase().starts_with(&heading))
+    }
+
+    /// Returns the type signature of the function, if one was documented.
+    pub fn type_signature(&self) -> Option<String> {
+        self.section("type")
+            .and_then(|s| code_blocks(&s.lines).into_iter().next())
+    }
+
+    /// Returns the contents of all code blocks within example sections.
+    pub fn examples(&self) -> Vec<String> {
+        self.sections
+            .iter()
+            .filter(|s| s.heading.to_lowercase().starts_with("example"))
+            .flat_map(|s| code_blocks(&s.lines))
+            .collect()
+    }
+}
+
+/// Extracts the contents of every fenced code block within the provided lines.
+pub fn code_blocks(lines: &[String]) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+
+    for line in lines {
+        if is_code_fence(line) {
+            match current.take() {
+                Some(block) => blocks.push(block.join("\n")),
+                None => current = Some(Vec::new()),
+            }
+        } else if let Some(block) = current.as_mut() {
+            block.push(line);
+        }
+    }
+
+    blocks
+}
+
+/// Joins the first non-empty paragraph of the provided lines into plain text.
+fn first_paragraph(lines: &[String]) -> String {
+    let mut in_fence = false;
+    lines
+        .iter()
+        .skip_while(|l| l.trim().is_empty())
+        .take_while(|l| {
+            if is_code_fence(l) {
+                in_fence = !in_fence;
+            }
+            !l.trim().is_empty() && !in_fence && !is_code_fence(l)
+        })
+        .map(|l| plain_text(l.trim()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Removes inline markdown markup (code spans, emphasis and links) from text.
+pub fn plain_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '`' | '*' => {}
+            '_' if result.is_empty() || result.ends_with(' ') => {}
+            '[' => {
+                let mut label = String::new();
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    label.push(c);
+                }
+                if chars.peek() == Some(&'(') {
+                    for c in chars.by_ref() {
+                        if c == ')' {
+                            break;
+                        }
+                    }
+                }
+                result.push_str(&plain_text(&label));
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"# String manipulation {#sec-functions-library-strings}
+Functions for working with strings.
+
+Second paragraph.
+
+## `lib.strings.concatStrings` {#function-library-lib.strings.concatStrings}
+
+Concatenate a list of strings.
+
+### Type
+
+```
+concatStrings :: [string] -> string
+```
+
+### Examples
+:::{.example}
+## `lib.strings.concatStrings` usage example
+
+```nix
+concatStrings ["foo" "bar"]
+=> "foobar"
+```
+:::
+
+## `lib.strings.hasPrefix` {#function-library-lib.strings.hasPrefix}
+
+Determine whether a string has given prefix.
+"#;
+
+    #[test]
+    fn test_parse_heading_with_anchor() {
+        let heading = parse_heading("## `lib.foo` {#function-library-lib.foo}").unwrap();
+        assert_eq!(heading.level, 2);
+        assert_eq!(heading.text, "`lib.foo`");
+        assert_eq!(heading.anchor, Some("function-library-lib.foo"));
+    }
+
+    #[test]
+    fn test_parse_heading_without_anchor() {
+        let heading = parse_heading("### Type").unwrap();
+        assert_eq!(heading.level, 3);
+        assert_eq!(heading.text, "Type");
+        assert_eq!(heading.anchor, None);
+    }
+
+    #[test]
+    fn test_parse_heading_rejects_non_headings() {
+        assert!(parse_heading("#not-a-heading").is_none());
+        assert!(parse_heading("plain text").is_none());
+        assert!(parse_heading("####### too deep").is_none());
+    }
+
+    #[test]
+    fn test_parse_page_structure() {
+        let page = DocPage::parse(PAGE);
+
+        assert_eq!(page.title, "String manipulation");
+        assert_eq!(
+            page.anchor.as_deref(),
+            Some("sec-functions-library-strings")
+        );
+        assert_eq!(page.description[0], "Functions for working with strings.");
+        assert_eq!(page.entries.len(), 2);
+
+        let concat = &page.entries[0];
+        assert_eq!(concat.name, "lib.strings.concatStrings");
+        assert_eq!(concat.line, 6);
+        assert_eq!(concat.summary(), "Concatenate a list of strings.");
+        assert_eq!(
+            concat.type_signature().as_deref(),
+            Some("concatStrings :: [string] -> string")
+        );
+        assert_eq!(
+            concat.examples(),
+            vec!["concatStrings [\"foo\" \"bar\"]\n=> \"foobar\"".to_string()]
+        );
+
+        let prefix = &page.entries[1];
+        assert_eq!(prefix.name, "lib.strings.hasPrefix");
+        assert_eq!(prefix.type_signature(), None);
+        assert!(prefix.examples().is_empty());
+    }
+
+    #[test]
+    fn test_parse_ignores_headings_in_code_blocks() {
+        let page = DocPage::parse("# Title\n\n```\n## `fake` {#fake}\n```\n");
+        assert!(page.entries.is_empty());
+    }
+
+    #[test]
+    fn test_plain_text_strips_markup() {
+        assert_eq!(
+            plain_text("Use `foo` with *care*, see [the docs](x.md#y)."),
+            "Use foo with care, see the docs."
+        );
+    }
+}
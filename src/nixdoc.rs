@@ -8,10 +8,13 @@ use std::{
     process::{Command, Stdio},
 };
 
-use anyhow::{Context, Result, anyhow};
 use typed_builder::TypedBuilder;
 
-use crate::mapping::{PathAction, PathMapping};
+use crate::{
+    error::{Error, Result},
+    fragment::DirectorySettings,
+    mapping::{PathAction, PathMapping, relative_to},
+};
 
 /// Builder for creating nixdoc commands.
 ///
@@ -33,20 +36,20 @@ struct Nixdoc<'a> {
     anchor_prefix: Option<&'a str>,
 }
 
-impl<'a> Into<Command> for Nixdoc<'a> {
-    fn into(self) -> Command {
+impl<'a> From<Nixdoc<'a>> for Command {
+    fn from(nixdoc: Nixdoc<'a>) -> Self {
         let mut command = Command::new("nixdoc");
         command
             .arg("--category")
-            .arg(self.category)
+            .arg(nixdoc.category)
             .arg("--description")
-            .arg(self.description)
+            .arg(nixdoc.description)
             .arg("--file")
-            .arg(self.file);
-        if let Some(prefix) = self.prefix {
+            .arg(nixdoc.file);
+        if let Some(prefix) = nixdoc.prefix {
             command.arg("--prefix").arg(prefix);
         }
-        if let Some(anchor) = self.anchor_prefix {
+        if let Some(anchor) = nixdoc.anchor_prefix {
             command.arg("--anchor-prefix").arg(anchor);
         }
 
@@ -63,6 +66,19 @@ impl<'a> Nixdoc<'a> {
     }
 }
 
+/// Documentation that was successfully generated for a single source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedDoc {
+    /// Path to the documented source file
+    pub source: PathBuf,
+    /// Path to the generated markdown file
+    pub destination: PathBuf,
+    /// The nixdoc category used for the source file
+    pub category: String,
+    /// The prefix used for generated identifiers
+    pub prefix: String,
+}
+
 /// Automated nixdoc documentation generator.
 ///
 /// This struct provides high-level automation for generating nixdoc documentation
@@ -77,6 +93,8 @@ pub struct AutoNixdoc<'a, M: PathMapping> {
     mapper: M,
     /// Input directory root for computing relative paths
     input_dir: PathBuf,
+    /// Category that categories of all documented files are nested under
+    category_root: Option<String>,
 }
 
 impl<'a, M: PathMapping> AutoNixdoc<'a, M> {
@@ -90,13 +108,28 @@ impl<'a, M: PathMapping> AutoNixdoc<'a, M> {
     /// * `mapper` - Path mapping strategy for determining output file locations
     pub fn new(prefix: &'a str, anchor_prefix: &'a str, input_dir: PathBuf, mapper: M) -> Self {
         AutoNixdoc {
-            prefix: prefix.into(),
-            anchor_prefix: anchor_prefix.into(),
+            prefix,
+            anchor_prefix,
             mapper,
             input_dir,
+            category_root: None,
         }
     }
 
+    /// Nests the categories of all documented files under `category_root`.
+    ///
+    /// For example, with a category root of `modules`, `lib/strings.nix` is documented with the
+    /// category `modules.lib.strings`.
+    pub fn with_category_root(mut self, category_root: Option<String>) -> Self {
+        self.category_root = category_root;
+        self
+    }
+
+    /// Returns the path mapping strategy used to locate output files.
+    pub fn mapper(&self) -> &M {
+        &self.mapper
+    }
+
     /// Generates documentation for a single source file.
     ///
     /// This function processes a source file and generates corresponding markdown
@@ -106,7 +139,7 @@ impl<'a, M: PathMapping> AutoNixdoc<'a, M> {
     /// Note that depending on the behavior of the mapping strategy, it's possible
     /// for this function to return successfully without generation output documentation.
     /// This would be the case if e.g. a mapping strategy decides that a specific
-    /// source file should be ignored.
+    /// source file should be ignored, in which case `None` is returned.
     ///
     /// # Arguments
     ///
@@ -121,31 +154,52 @@ impl<'a, M: PathMapping> AutoNixdoc<'a, M> {
     /// - The source file cannot be read
     /// - The output directory cannot be created
     /// - The nixdoc command fails
-    pub fn execute<P: AsRef<Path>>(&self, config: &M::Config, path_ref: P) -> Result<()> {
+    pub fn execute<P: AsRef<Path>>(
+        &self,
+        config: &M::Config,
+        path_ref: P,
+    ) -> Result<Option<GeneratedDoc>> {
+        self.execute_with(config, path_ref, &DirectorySettings::default())
+    }
+
+    /// Generates documentation for a single source file, overriding the prefixes with any that
+    /// are set in `settings`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as [`AutoNixdoc::execute`].
+    pub fn execute_with<P: AsRef<Path>>(
+        &self,
+        config: &M::Config,
+        path_ref: P,
+        settings: &DirectorySettings,
+    ) -> Result<Option<GeneratedDoc>> {
         let path = path_ref.as_ref();
 
-        let path_action = self
-            .mapper
-            .resolve(config, path)
-            .with_context(|| "path mapping failed")?;
+        let path_action = self.mapper.resolve(config, path)?;
 
         match path_action {
-            PathAction::Skip => Ok(()),
-            PathAction::OutputTo(dest_path) => self.output_to(path, dest_path),
+            PathAction::Skip => Ok(None),
+            PathAction::OutputTo(dest_path) => self.output_to(path, dest_path, settings).map(Some),
         }
     }
 
     fn extract_category(&self, path: &Path) -> Result<String> {
-        let relative_path = path
-            .strip_prefix(&self.input_dir)
-            .with_context(|| "source path is not within input directory")?;
+        let relative_path =
+            relative_to(&self.input_dir, path).ok_or_else(|| Error::OutsideInputDir {
+                path: path.to_path_buf(),
+                input_dir: self.input_dir.clone(),
+            })?;
 
         let file_stem = relative_path
             .file_stem()
             .and_then(OsStr::to_str)
-            .with_context(|| "source path had no file name")?;
+            .ok_or_else(|| Error::MappingFailed {
+                path: path.to_path_buf(),
+                reason: "source path had no file name".to_string(),
+            })?;
 
-        let parent_components: Vec<&str> = relative_path
+        let mut parent_components: Vec<&str> = relative_path
             .parent()
             .map(|p| {
                 p.components()
@@ -159,6 +213,9 @@ impl<'a, M: PathMapping> AutoNixdoc<'a, M> {
                     .collect()
             })
             .unwrap_or_default();
+        if let Some(category_root) = &self.category_root {
+            parent_components.insert(0, category_root);
+        }
 
         let category = if parent_components.is_empty() {
             file_stem.to_string()
@@ -169,63 +226,92 @@ impl<'a, M: PathMapping> AutoNixdoc<'a, M> {
         Ok(category)
     }
 
-    fn output_to(&self, path: &Path, dest_path: PathBuf) -> Result<()> {
-        let path_str = path
-            .to_str()
-            .with_context(|| "source path was not valid unicode")?;
+    fn output_to(
+        &self,
+        path: &Path,
+        dest_path: PathBuf,
+        settings: &DirectorySettings,
+    ) -> Result<GeneratedDoc> {
+        let path_str = path.to_str().ok_or_else(|| Error::InvalidUnicode {
+            path: path.to_path_buf(),
+        })?;
 
         let category = self.extract_category(path)?;
 
         if let Some(parent) = dest_path.parent() {
-            std::fs::create_dir_all(&parent).with_context(|| {
-                format!(
-                    "Failed to create documentation directory: {}",
-                    parent.display()
-                )
+            std::fs::create_dir_all(parent).map_err(|source| Error::Io {
+                operation: "create documentation directory",
+                path: parent.to_path_buf(),
+                source,
             })?;
         }
 
-        let dest_file = File::create(&dest_path)
-            .with_context(|| format!("Failed to create output file: {}", dest_path.display()))?;
+        let dest_file = File::create(&dest_path).map_err(|source| Error::Io {
+            operation: "create output file",
+            path: dest_path.clone(),
+            source,
+        })?;
 
-        let file = File::open(path)?;
+        let read_error = |source| Error::Io {
+            operation: "read input file",
+            path: path.to_path_buf(),
+            source,
+        };
+        let file = File::open(path).map_err(read_error)?;
         let reader = BufReader::new(file);
         // TODO: description extraction strategy?
         let desc = reader
             .lines()
             .nth(1)
             .transpose()
-            .with_context(|| format!("Failed to read input file: {}", path_str))?
+            .map_err(read_error)?
             .unwrap_or_default();
+        let prefix = settings.prefix.as_deref().unwrap_or(self.prefix);
+        let anchor_prefix = settings
+            .anchor_prefix
+            .as_deref()
+            .unwrap_or(self.anchor_prefix);
 
         let nixdoc = Nixdoc::builder()
             .file(path_str)
             .category(&category)
             .description(&desc)
-            .prefix(&self.prefix)
-            .anchor_prefix(&self.anchor_prefix)
+            .prefix(prefix)
+            .anchor_prefix(anchor_prefix)
             .build();
 
         let output = nixdoc
             .into_command()
             .stdout(Stdio::from(dest_file))
             .output()
-            .with_context(|| "nixdoc command execution failed")?;
+            .map_err(|source| match source.kind() {
+                std::io::ErrorKind::NotFound => Error::NixdocMissing,
+                _ => Error::Io {
+                    operation: "run nixdoc for",
+                    path: path.to_path_buf(),
+                    source,
+                },
+            })?;
 
         if output.status.success() {
-            Ok(())
+            Ok(GeneratedDoc {
+                source: path.to_path_buf(),
+                destination: dest_path,
+                category,
+                prefix: prefix.to_string(),
+            })
         } else {
-            Err(anyhow!(
-                "nixdoc command error: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ))
+            Err(Error::NixdocFailed {
+                path: path.to_path_buf(),
+                status: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            })
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use anyhow::{Result, anyhow};
     use std::{
         ffi::OsStr, fs, os::unix::ffi::OsStrExt, os::unix::fs::PermissionsExt, path::PathBuf,
     };
@@ -234,6 +320,7 @@ mod tests {
     use super::*;
     use crate::{
         cli::{FailureBehavior, LogLevel},
+        error::ErrorKind,
         mapping::{AutoMapping, BaselineConfig, PathMapping},
     };
 
@@ -262,9 +349,11 @@ mod tests {
         let result = nixdoc.execute(&Default::default(), &test_nix_file);
 
         match result {
-            Ok(()) => {
+            Ok(Some(generated)) => {
                 let expected_output = output_dir.join("test-lib.md");
                 assert!(expected_output.exists(), "Output file should be created");
+                assert_eq!(generated.destination, expected_output);
+                assert_eq!(generated.category, "test-lib");
 
                 let content = fs::read_to_string(&expected_output).unwrap();
                 assert!(
@@ -276,6 +365,7 @@ mod tests {
                     "Output file should contain module description"
                 );
             }
+            Ok(None) => panic!("Source file should not have been skipped"),
             Err(e) => panic!("Unexpected error: {:?}", e),
         }
     }
@@ -291,8 +381,9 @@ mod tests {
         let result = nixdoc.execute(&Default::default(), &nonexistent_file);
 
         assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("No such file"));
+        let error = result.unwrap_err();
+        let cause = std::error::Error::source(&error).expect("Error should have a cause");
+        assert!(cause.to_string().contains("No such file"));
     }
 
     #[test]
@@ -308,8 +399,7 @@ mod tests {
         let result = nixdoc.execute(&Default::default(), &invalid_file);
 
         assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("source path was not valid unicode"));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidUnicode);
     }
 
     #[test]
@@ -341,7 +431,7 @@ mod tests {
         assert!(result.is_err());
         let error_msg = result.unwrap_err().to_string();
         assert!(
-            error_msg.contains("Failed to create output file")
+            error_msg.contains("failed to create output file")
                 || error_msg.contains("Permission denied")
         );
     }
@@ -358,8 +448,8 @@ mod tests {
         let result = nixdoc.execute(&Default::default(), &empty_file);
 
         match result {
-            Ok(()) => panic!("Nixdoc execution should've failed"),
-            Err(e) if e.to_string().contains("nixdoc command error") => {
+            Ok(_) => panic!("Nixdoc execution should've failed"),
+            Err(Error::NixdocFailed { .. }) => {
                 println!("nixdoc command failed on empty file, which is expected behavior");
             }
             Err(e) => panic!("Unexpected error: {:?}", e),
@@ -368,7 +458,7 @@ mod tests {
 
     #[test]
     fn test_nixdoc_execute_path_mapping_failure() {
-        #[derive(Default, serde::Deserialize)]
+        #[derive(Default, serde::Deserialize, schemars::JsonSchema)]
         struct FailingMapperConfig;
 
         impl BaselineConfig for FailingMapperConfig {
@@ -391,8 +481,11 @@ mod tests {
         impl PathMapping for FailingMapper {
             type Config = FailingMapperConfig;
 
-            fn resolve(&self, _config: &Self::Config, _path: &Path) -> Result<PathAction> {
-                Err(anyhow!("Mock path mapping failure"))
+            fn resolve(&self, _config: &Self::Config, path: &Path) -> Result<PathAction> {
+                Err(Error::MappingFailed {
+                    path: path.to_path_buf(),
+                    reason: "Mock path mapping failure".to_string(),
+                })
             }
         }
 
@@ -405,8 +498,9 @@ mod tests {
         let nixdoc = AutoNixdoc::new("lib", "lib-", input_dir.clone(), failing_mapper);
         let result = nixdoc.execute(&Default::default(), &test_file);
 
-        assert!(result.is_err());
-        let error_msg = format!("{:?}", result.unwrap_err());
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::MappingFailed);
+        let error_msg = error.to_string();
         assert!(error_msg.contains("path mapping failed"));
         assert!(error_msg.contains("Mock path mapping failure"));
     }
@@ -588,4 +682,28 @@ mod tests {
             content
         );
     }
+
+    #[test]
+    fn test_category_extraction_with_category_root() {
+        const TEST_NIX_CONTENT: &str = include_str!("../resources/test-lib.nix");
+
+        let (_temp_dir, input_dir, output_dir) = setup_test_dirs();
+
+        let subdir = input_dir.join("utils");
+        fs::create_dir_all(&subdir).unwrap();
+
+        let test_nix_file = subdir.join("helpers.nix");
+        fs::write(&test_nix_file, TEST_NIX_CONTENT).unwrap();
+
+        let mapping = AutoMapping::new(&input_dir, &output_dir);
+        let nixdoc = AutoNixdoc::new("lib", "lib-", input_dir.clone(), mapping)
+            .with_category_root(Some("modules".to_string()));
+
+        let generated = nixdoc
+            .execute(&Default::default(), &test_nix_file)
+            .expect("Failed to execute")
+            .expect("Documentation should be generated");
+
+        assert_eq!(generated.category, "modules.utils.helpers");
+    }
 }
@@ -0,0 +1,607 @@
+//! Lightweight scanning of Nix source files.
+//!
+//! This is not a full Nix parser. It tokenizes just enough of the language (comments, strings,
+//! brackets and bindings) to locate the attribute set exported by a library file along with the
+//! comments attached to each of its attributes, which is all that documentation tooling needs.
+
+/// The syntactic kind of a comment.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CommentKind {
+    /// A `/** ... */` doc comment understood by nixdoc
+    Doc,
+    /// A regular `/* ... */` block comment
+    Block,
+    /// A `# ...` line comment
+    Line,
+}
+
+/// A comment within a Nix source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// The syntactic kind of the comment
+    pub kind: CommentKind,
+    /// 1-based line on which the comment starts
+    pub line: usize,
+    /// 1-based line on which the comment ends
+    pub end_line: usize,
+    /// Comment content with delimiters removed
+    pub text: String,
+}
+
+/// An attribute exported by a Nix source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    /// Attribute path of the binding (e.g. `foo` or `foo.bar`)
+    pub name: String,
+    /// 1-based line on which the binding starts
+    pub line: usize,
+    /// Comment immediately preceding the binding, if any
+    pub comment: Option<Comment>,
+    /// Whether the attribute was introduced with `inherit`
+    pub inherited: bool,
+}
+
+impl Attribute {
+    /// Returns true if the attribute has a non-empty doc comment.
+    pub fn is_documented(&self) -> bool {
+        self.comment
+            .as_ref()
+            .is_some_and(|c| c.kind == CommentKind::Doc && !c.text.trim().is_empty())
+    }
+}
+
+/// The documentation-relevant structure of a Nix source file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NixSource {
+    /// All comments within the file, in source order
+    pub comments: Vec<Comment>,
+    /// Attributes of the attribute set exported by the file, in source order
+    pub attributes: Vec<Attribute>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Comment(Comment),
+    Ident(String),
+    Str(String),
+    Punct(char),
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Lexer {
+            chars: source.chars().peekable(),
+            line: 1,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.chars.peek() == Some(&expected) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn tokenize(mut self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        while let Some(token) = self.next_token() {
+            tokens.push(token);
+        }
+        tokens
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.bump();
+        }
+
+        let line = self.line;
+        let c = self.bump()?;
+        let kind = match c {
+            '#' => {
+                let mut text = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    text.push(c);
+                    self.bump();
+                }
+                TokenKind::Comment(Comment {
+                    kind: CommentKind::Line,
+                    line,
+                    end_line: line,
+                    text,
+                })
+            }
+            '/' if self.eat('*') => self.block_comment(line),
+            '"' => TokenKind::Str(self.string()),
+            '\'' if self.eat('\'') => {
+                self.indented_string();
+                TokenKind::Other
+            }
+            '$' if self.eat('{') => {
+                self.interpolation();
+                TokenKind::Other
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::from(c);
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_alphanumeric() || matches!(c, '_' | '\'' | '-') {
+                        ident.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                TokenKind::Ident(ident)
+            }
+            '{' | '}' | '[' | ']' | '(' | ')' | '=' | ';' | ':' | '.' | '@' | ',' => {
+                TokenKind::Punct(c)
+            }
+            _ => TokenKind::Other,
+        };
+
+        Some(Token { kind, line })
+    }
+
+    fn block_comment(&mut self, line: usize) -> TokenKind {
+        let mut text = String::new();
+        let mut kind = CommentKind::Block;
+
+        // `/**/` is an empty block comment rather than the start of a doc comment
+        if self.chars.peek() == Some(&'*') {
+            self.bump();
+            if self.chars.peek() == Some(&'/') {
+                self.bump();
+                return TokenKind::Comment(Comment {
+                    kind,
+                    line,
+                    end_line: self.line,
+                    text,
+                });
+            }
+            kind = CommentKind::Doc;
+        }
+
+        while let Some(c) = self.bump() {
+            if c == '*' && self.eat('/') {
+                break;
+            }
+            text.push(c);
+        }
+
+        TokenKind::Comment(Comment {
+            kind,
+            line,
+            end_line: self.line,
+            text,
+        })
+    }
+
+    /// Consumes a double-quoted string, returning its literal (non-interpolated) content.
+    fn string(&mut self) -> String {
+        let mut content = String::new();
+        while let Some(c) = self.bump() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = self.bump() {
+                        content.push(escaped);
+                    }
+                }
+                '$' if self.eat('{') => self.interpolation(),
+                _ => content.push(c),
+            }
+        }
+        content
+    }
+
+    fn indented_string(&mut self) {
+        while let Some(c) = self.bump() {
+            match c {
+                '\'' if self.eat('\'') => {
+                    // `'''`, `''$` and `''\x` are escapes rather than terminators
+                    if self.eat('\'') || self.eat('$') {
+                        continue;
+                    }
+                    if self.eat('\\') {
+                        self.bump();
+                        continue;
+                    }
+                    break;
+                }
+                '$' if self.eat('{') => self.interpolation(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Consumes the remainder of a `${ ... }` interpolation.
+    fn interpolation(&mut self) {
+        let mut depth = 1;
+        while let Some(token) = self.next_token() {
+            match token.kind {
+                TokenKind::Punct('{') => depth += 1,
+                TokenKind::Punct('}') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_punct(token: Option<&Token>, c: char) -> bool {
+    token.is_some_and(|t| t.kind == TokenKind::Punct(c))
+}
+
+fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+    token.is_some_and(|t| matches!(&t.kind, TokenKind::Ident(i) if i == keyword))
+}
+
+/// Returns the index of the bracket closing the one opened at `open`, if it is closed.
+fn matching_close(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        match token.kind {
+            TokenKind::Punct('{' | '[' | '(') => depth += 1,
+            TokenKind::Punct('}' | ']' | ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the index just past the end of an expression starting at `start`.
+///
+/// The expression ends at the first `;` (or closing bracket) that isn't nested within brackets
+/// or a `let ... in` block.
+fn skip_expression(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut lets = 0usize;
+    let mut index = start;
+
+    while let Some(token) = tokens.get(index) {
+        match &token.kind {
+            TokenKind::Punct('{' | '[' | '(') => depth += 1,
+            TokenKind::Punct('}' | ']' | ')') => {
+                if depth == 0 {
+                    return index;
+                }
+                depth -= 1;
+            }
+            TokenKind::Punct(';') if depth == 0 && lets == 0 => return index + 1,
+            TokenKind::Ident(i) if depth == 0 && i == "let" => lets += 1,
+            TokenKind::Ident(i) if depth == 0 && i == "in" => lets = lets.saturating_sub(1),
+            _ => {}
+        }
+        index += 1;
+    }
+
+    index
+}
+
+/// Finds the bounds of the attribute set exported by the file.
+///
+/// Lambda formals, `let ... in` blocks and `with`/`assert` prefixes are skipped; the first
+/// remaining attribute set is considered to be the exported one.
+fn find_exported_set(tokens: &[Token]) -> Option<(usize, usize)> {
+    let mut index = 0;
+    while let Some(token) = tokens.get(index) {
+        match &token.kind {
+            TokenKind::Ident(i) if i == "let" => {
+                let mut lets = 0usize;
+                while let Some(token) = tokens.get(index) {
+                    if is_keyword(Some(token), "let") {
+                        lets += 1;
+                    } else if is_keyword(Some(token), "in") {
+                        lets -= 1;
+                        if lets == 0 {
+                            break;
+                        }
+                    }
+                    index += 1;
+                }
+            }
+            TokenKind::Ident(i) if i == "with" || i == "assert" => {
+                index = skip_expression(tokens, index + 1) - 1;
+            }
+            TokenKind::Punct('{') => {
+                // An unclosed set exports nothing
+                let close = matching_close(tokens, index)?;
+                // Comments may sit between a function's formals and its colon
+                let next = tokens
+                    .iter()
+                    .skip(close + 1)
+                    .find(|t| !matches!(t.kind, TokenKind::Comment(_)));
+                if is_punct(next, ':') || is_punct(next, '@') {
+                    index = close;
+                } else {
+                    return Some((index, close));
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+
+    None
+}
+
+impl NixSource {
+    /// Scans the provided Nix source code.
+    pub fn parse(source: &str) -> Self {
+        let tokens = Lexer::new(source).tokenize();
+        let comments = tokens
+            .iter()
+            .filter_map(|t| match &t.kind {
+                TokenKind::Comment(c) => Some(c.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let attributes = match find_exported_set(&tokens) {
+            Some((open, close)) => Self::bindings(&tokens[open + 1..close]),
+            None => Vec::new(),
+        };
+
+        NixSource {
+            comments,
+            attributes,
+        }
+    }
+
+    /// Returns the exported attribute with the provided name, if any.
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes.iter().find(|a| a.name == name)
+    }
+
+    fn bindings(tokens: &[Token]) -> Vec<Attribute> {
+        let mut attributes = Vec::new();
+        let mut comment: Option<Comment> = None;
+        // Comments on the same line as the end of the previous binding belong to that binding
+        let mut previous_end_line = 0;
+        let mut index = 0;
+
+        while let Some(token) = tokens.get(index) {
+            match &token.kind {
+                TokenKind::Comment(c) => {
+                    if c.line != previous_end_line {
+                        comment = Some(c.clone());
+                    }
+                    index += 1;
+                }
+                TokenKind::Ident(i) if i == "inherit" => {
+                    let end = skip_expression(tokens, index + 1);
+                    let mut cursor = index + 1;
+                    while cursor < end {
+                        if is_punct(tokens.get(cursor), '(') {
+                            cursor = matching_close(tokens, cursor).unwrap_or(end);
+                        } else if let Some(TokenKind::Ident(name) | TokenKind::Str(name)) =
+                            tokens.get(cursor).map(|t| &t.kind)
+                        {
+                            attributes.push(Attribute {
+                                name: name.clone(),
+                                line: tokens[cursor].line,
+                                comment: None,
+                                inherited: true,
+                            });
+                        }
+                        cursor += 1;
+                    }
+                    previous_end_line = tokens.get(end.saturating_sub(1)).map_or(0, |t| t.line);
+                    comment = None;
+                    index = end;
+                }
+                TokenKind::Ident(_) | TokenKind::Str(_) => {
+                    let line = token.line;
+                    let mut path = Vec::new();
+                    while let Some(token) = tokens.get(index) {
+                        match &token.kind {
+                            TokenKind::Ident(part) | TokenKind::Str(part) => {
+                                path.push(part.as_str())
+                            }
+                            TokenKind::Punct('.') | TokenKind::Other => {}
+                            _ => break,
+                        }
+                        index += 1;
+                    }
+
+                    if is_punct(tokens.get(index), '=') {
+                        attributes.push(Attribute {
+                            name: path.join("."),
+                            line,
+                            comment: comment.take(),
+                            inherited: false,
+                        });
+                    }
+
+                    let end = skip_expression(tokens, index);
+                    // A trailing identifier without a value ends the token stream
+                    previous_end_line = tokens
+                        .get(end.saturating_sub(1).max(index))
+                        .map_or(line, |t| t.line);
+                    comment = None;
+                    index = end.max(index + 1);
+                }
+                _ => {
+                    comment = None;
+                    index += 1;
+                }
+            }
+        }
+
+        attributes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_test_library() {
+        let source = NixSource::parse(include_str!("../resources/test-lib.nix"));
+
+        assert_eq!(source.comments.len(), 2);
+        assert_eq!(source.comments[0].kind, CommentKind::Doc);
+        assert_eq!(source.comments[0].text.trim(), "Utility functions");
+
+        let names: Vec<_> = source.attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["simpleOption", "mergeAttrs"]);
+
+        let simple_option = source.attribute("simpleOption").unwrap();
+        assert_eq!(simple_option.line, 10);
+        assert_eq!(
+            simple_option.comment.as_ref().map(|c| c.text.trim()),
+            Some("A simple option")
+        );
+
+        let merge_attrs = source.attribute("mergeAttrs").unwrap();
+        assert_eq!(merge_attrs.line, 17);
+        assert!(merge_attrs.comment.is_none());
+    }
+
+    #[test]
+    fn test_parse_comment_kinds() {
+        let source = NixSource::parse(
+            r#"{
+  # line comment
+  a = 1;
+  /* block comment */
+  b = 2;
+  /** doc comment */
+  c = 3;
+  /**/
+  d = 4;
+}"#,
+        );
+
+        let kinds: Vec<_> = source
+            .attributes
+            .iter()
+            .map(|a| a.comment.as_ref().map(|c| c.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(CommentKind::Line),
+                Some(CommentKind::Block),
+                Some(CommentKind::Doc),
+                Some(CommentKind::Block),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_skips_let_and_nested_sets() {
+        let source = NixSource::parse(
+            r#"{ lib, ... }@args:
+let
+  inner = { hidden = 1; };
+  other = let x = 1; in x;
+in
+with lib;
+rec {
+  /** Documented */
+  outer = { nested = 1; };
+  value = let y = { z = 2; }; in y;
+  "quoted" = "string with ${interpolation { a = 1; }} and ; semicolon";
+  indented = ''
+    text ''${escaped} with }
+  '';
+  path.to.attr = 3;
+  inherit (lib) mkOption mkIf;
+  inherit inner;
+}"#,
+        );
+
+        let names: Vec<_> = source.attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "outer",
+                "value",
+                "quoted",
+                "indented",
+                "path.to.attr",
+                "mkOption",
+                "mkIf",
+                "inner",
+            ]
+        );
+        assert!(source.attribute("outer").unwrap().comment.is_some());
+        assert!(source.attribute("mkIf").unwrap().inherited);
+        assert_eq!(source.attribute("path.to.attr").unwrap().line, 15);
+    }
+
+    #[test]
+    fn test_trailing_comment_belongs_to_previous_binding() {
+        let source = NixSource::parse("{\n  a = 1; # about a\n  b = 2;\n}");
+        assert!(source.attribute("b").unwrap().comment.is_none());
+    }
+
+    #[test]
+    fn test_parse_without_attribute_set() {
+        let source = NixSource::parse("x: x + 1");
+        assert!(source.attributes.is_empty());
+    }
+
+    #[test]
+    fn test_parse_comment_before_colon() {
+        let source = NixSource::parse("{ lib } /* args */ :\n{ a = 1; }");
+        assert!(source.attribute("a").is_some());
+        assert!(source.attribute("lib").is_none());
+    }
+
+    #[test]
+    fn test_parse_trailing_identifier() {
+        let source = NixSource::parse("{ a = 1; b }");
+        assert!(source.attribute("a").is_some());
+        assert!(source.attribute("b").is_none());
+    }
+
+    #[test]
+    fn test_parse_unclosed_set() {
+        for input in ["{", "x: {", "in {", "@{"] {
+            assert!(NixSource::parse(input).attributes.is_empty(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_unclosed_inherit_source() {
+        let source = NixSource::parse("{ inherit (lib a b; }");
+        assert!(source.attribute("a").is_none());
+    }
+}
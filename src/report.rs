@@ -0,0 +1,266 @@
+//! Per-file outcomes of a documentation generation run.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Serialize, Serializer};
+
+use crate::{cli::FailureBehavior, error::ErrorKind};
+
+/// What happened to a single path during a run.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileStatus {
+    /// Documentation was generated and written
+    Written,
+    /// The path wasn't selected by path identification
+    SkippedByIdentification,
+    /// The path mapping chose to skip the path
+    SkippedByMapping,
+    /// Documentation generation failed
+    Failed,
+}
+
+/// Outcome of a single path during a run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileReport {
+    /// The processed path
+    pub path: PathBuf,
+    /// What happened to the path
+    pub status: FileStatus,
+    /// Where documentation was written, if it was
+    pub destination: Option<PathBuf>,
+    /// The nixdoc category of the documentation, if it was written
+    pub category: Option<String>,
+    /// Why generation failed, if it did
+    pub error: Option<String>,
+    /// The kind of error that generation failed with, if it did
+    pub error_kind: Option<ErrorKind>,
+    /// How the failure was handled, if generation failed
+    #[serde(serialize_with = "serialize_failure_behavior")]
+    pub on_failure: Option<FailureBehavior>,
+    /// Time spent processing the path, in milliseconds
+    pub duration_ms: f64,
+}
+
+impl FileReport {
+    /// Creates a report for a path without a destination, category or error.
+    pub fn new(path: &Path, status: FileStatus, duration: Duration) -> Self {
+        FileReport {
+            path: path.to_path_buf(),
+            status,
+            destination: None,
+            category: None,
+            error: None,
+            error_kind: None,
+            on_failure: None,
+            duration_ms: duration.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// Serializes a failure behavior by its kebab-case name on the CLI, like all other values of the
+/// report, rather than its PascalCase name in configuration files.
+fn serialize_failure_behavior<S: Serializer>(
+    on_failure: &Option<FailureBehavior>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let name = on_failure
+        .and_then(|behavior| behavior.to_possible_value())
+        .map(|value| value.get_name().to_string());
+    name.serialize(serializer)
+}
+
+/// Counts of file outcomes across a run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Summary {
+    /// Files selected by path identification
+    pub processed: usize,
+    /// Files for which documentation was written
+    pub written: usize,
+    /// Files left out by path identification, which aren't counted as processed
+    pub skipped_by_identification: usize,
+    /// Files that the path mapping decided not to document
+    pub skipped_by_mapping: usize,
+    /// Files for which documentation could not be generated
+    pub failed: usize,
+    /// Time spent processing all files, in milliseconds
+    pub duration_ms: f64,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Processed {} file(s) in {:.2}s: {} written, {} skipped by mapping, {} failed \
+             ({} skipped by identification)",
+            self.processed,
+            self.duration_ms / 1000.0,
+            self.written,
+            self.skipped_by_mapping,
+            self.failed,
+            self.skipped_by_identification
+        )
+    }
+}
+
+/// Outcomes of every path encountered during a run.
+#[derive(Debug, Default)]
+pub struct RunReport {
+    files: Vec<FileReport>,
+    duration: Duration,
+}
+
+impl RunReport {
+    /// Records the outcome of a single path.
+    pub fn record(&mut self, file: FileReport) {
+        self.files.push(file);
+    }
+
+    /// Outcomes of all recorded paths, in the order they were recorded.
+    pub fn files(&self) -> &[FileReport] {
+        &self.files
+    }
+
+    /// Counts the recorded failures that were logged rather than skipped.
+    pub fn logged_failures(&self) -> usize {
+        self.files
+            .iter()
+            .filter(|file| file.on_failure == Some(FailureBehavior::Log))
+            .count()
+    }
+
+    /// Sets the total duration of the run.
+    pub fn finish(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+
+    /// Counts the outcomes of all recorded paths.
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary {
+            duration_ms: self.duration.as_secs_f64() * 1000.0,
+            ..Default::default()
+        };
+
+        for file in &self.files {
+            match file.status {
+                FileStatus::Written => summary.written += 1,
+                FileStatus::SkippedByIdentification => summary.skipped_by_identification += 1,
+                FileStatus::SkippedByMapping => summary.skipped_by_mapping += 1,
+                FileStatus::Failed => summary.failed += 1,
+            }
+        }
+        summary.processed = summary.written + summary.skipped_by_mapping + summary.failed;
+
+        summary
+    }
+
+    /// Writes the summary and per-file outcomes as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the report cannot be written.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        #[derive(Serialize)]
+        struct Json<'a> {
+            summary: Summary,
+            files: &'a [FileReport],
+        }
+
+        let json = serde_json::to_string_pretty(&Json {
+            summary: self.summary(),
+            files: &self.files,
+        })
+        .with_context(|| "Failed to serialize run report")?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write run report: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> RunReport {
+        let mut report = RunReport::default();
+        report.record(FileReport {
+            destination: Some(PathBuf::from("out/strings.md")),
+            category: Some("strings".to_string()),
+            ..FileReport::new(
+                Path::new("lib/strings.nix"),
+                FileStatus::Written,
+                Duration::from_millis(20),
+            )
+        });
+        report.record(FileReport::new(
+            Path::new("lib/README.md"),
+            FileStatus::SkippedByIdentification,
+            Duration::ZERO,
+        ));
+        report.record(FileReport::new(
+            Path::new("lib/ignored.nix"),
+            FileStatus::SkippedByMapping,
+            Duration::ZERO,
+        ));
+        report.record(FileReport {
+            error: Some("nixdoc command error".to_string()),
+            error_kind: Some(ErrorKind::NixdocFailed),
+            on_failure: Some(FailureBehavior::Log),
+            ..FileReport::new(
+                Path::new("lib/broken.nix"),
+                FileStatus::Failed,
+                Duration::from_millis(5),
+            )
+        });
+        report.finish(Duration::from_millis(1500));
+        report
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = report().summary();
+
+        assert_eq!(
+            summary,
+            Summary {
+                processed: 3,
+                written: 1,
+                skipped_by_identification: 1,
+                skipped_by_mapping: 1,
+                failed: 1,
+                duration_ms: 1500.0,
+            }
+        );
+        assert_eq!(report().logged_failures(), 1);
+        assert_eq!(
+            summary.to_string(),
+            "Processed 3 file(s) in 1.50s: 1 written, 1 skipped by mapping, 1 failed \
+             (1 skipped by identification)"
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("report.json");
+        report().write(&path).unwrap();
+
+        let value: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value["summary"]["failed"], 1);
+        assert_eq!(value["files"][0]["status"], "written");
+        assert_eq!(value["files"][0]["destination"], "out/strings.md");
+        assert_eq!(value["files"][0]["category"], "strings");
+        assert_eq!(value["files"][0]["duration_ms"], 20.0);
+        assert_eq!(value["files"][1]["status"], "skipped-by-identification");
+        assert_eq!(value["files"][3]["error"], "nixdoc command error");
+        assert_eq!(value["files"][3]["error_kind"], "nixdoc-failed");
+        assert_eq!(value["files"][3]["on_failure"], "log");
+        assert_eq!(value["files"][0]["on_failure"], serde_json::Value::Null);
+    }
+}
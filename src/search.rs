@@ -0,0 +1,180 @@
+//! Client-side search index generation.
+//!
+//! The search index is written as a single JSON document with the following schema:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "documents": [
+//!     {
+//!       "id": "lib.strings.concatStrings",
+//!       "name": "concatStrings",
+//!       "category": "lib.strings",
+//!       "anchor": "function-library-lib.strings.concatStrings",
+//!       "path": "strings.md",
+//!       "summary": "Concatenate a list of strings."
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `id` is the fully-qualified name of the function and is unique within the index, so the
+//! `documents` array can be fed directly to lunr or elasticlunr using `id` as the document
+//! reference. `path` is relative to the documentation output directory and always uses forward
+//! slashes; `path#anchor` links directly to the function documentation.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::markdown::DocPage;
+
+/// The name of the search index file written to the output directory.
+pub const SEARCH_INDEX_FILE: &str = "search-index.json";
+
+/// Version of the search index schema; incremented on incompatible changes.
+const SCHEMA_VERSION: u32 = 1;
+
+/// A search index over all documented functions.
+#[derive(Debug, Serialize)]
+pub struct SearchIndex {
+    version: u32,
+    documents: Vec<SearchDocument>,
+}
+
+/// A single searchable function.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct SearchDocument {
+    /// Fully-qualified name of the function (prefix + category + attribute)
+    pub id: String,
+    /// Attribute name of the function
+    pub name: String,
+    /// Fully-qualified name of the category containing the function
+    pub category: String,
+    /// Anchor of the function heading within its page
+    pub anchor: String,
+    /// Path of the page containing the function, relative to the output directory
+    pub path: String,
+    /// Plain-text summary of the function
+    pub summary: String,
+}
+
+impl Default for SearchIndex {
+    fn default() -> Self {
+        SearchIndex {
+            version: SCHEMA_VERSION,
+            documents: Vec::new(),
+        }
+    }
+}
+
+impl SearchIndex {
+    /// Adds every documented function of a page to the index.
+    ///
+    /// Functions without an anchor cannot be linked to and are omitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `category` - Fully-qualified name of the category documented by the page
+    /// * `path` - Path of the page relative to the output directory
+    /// * `page` - The parsed documentation page
+    pub fn add_page(&mut self, category: &str, path: &Path, page: &DocPage) {
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        for entry in &page.entries {
+            let Some(anchor) = &entry.anchor else {
+                continue;
+            };
+
+            let name = entry
+                .name
+                .strip_prefix(category)
+                .and_then(|n| n.strip_prefix('.'))
+                .unwrap_or(&entry.name);
+
+            self.documents.push(SearchDocument {
+                id: entry.name.clone(),
+                name: name.to_string(),
+                category: category.to_string(),
+                anchor: anchor.clone(),
+                path: path.clone(),
+                summary: entry.summary(),
+            });
+        }
+    }
+
+    /// Writes the index as JSON to the provided path.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write search index: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const PAGE: &str = r#"# Strings {#sec-functions-library-strings}
+
+## `lib.strings.concatStrings` {#function-library-lib.strings.concatStrings}
+
+Concatenate a list of `strings`.
+
+Further details.
+
+## `lib.strings.hasPrefix` {#function-library-lib.strings.hasPrefix}
+
+Determine whether a string has given prefix.
+"#;
+
+    #[test]
+    fn test_add_page_documents() {
+        let mut index = SearchIndex::default();
+        index.add_page(
+            "lib.strings",
+            &PathBuf::from("text/strings.md"),
+            &DocPage::parse(PAGE),
+        );
+
+        assert_eq!(index.documents.len(), 2);
+        assert_eq!(
+            index.documents[0],
+            SearchDocument {
+                id: "lib.strings.concatStrings".to_string(),
+                name: "concatStrings".to_string(),
+                category: "lib.strings".to_string(),
+                anchor: "function-library-lib.strings.concatStrings".to_string(),
+                path: "text/strings.md".to_string(),
+                summary: "Concatenate a list of strings.".to_string(),
+            }
+        );
+        assert_eq!(index.documents[1].name, "hasPrefix");
+    }
+
+    #[test]
+    fn test_write_schema() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(SEARCH_INDEX_FILE);
+
+        let mut index = SearchIndex::default();
+        index.add_page(
+            "lib.strings",
+            &PathBuf::from("strings.md"),
+            &DocPage::parse(PAGE),
+        );
+        index.write(&path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["documents"][1]["id"], "lib.strings.hasPrefix");
+        assert_eq!(json["documents"][1]["path"], "strings.md");
+    }
+}
@@ -0,0 +1,173 @@
+//! Links from generated documentation back to the documented source code.
+
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::debug;
+
+use crate::{markdown::DocPage, nixsource::NixSource};
+
+/// URL template used when none is configured, matching GitHub, Gitea and Forgejo.
+pub const DEFAULT_URL_TEMPLATE: &str = "{repo}/blob/{rev}/{path}#L{line}";
+
+/// Generates and injects "Source" links for documented functions.
+///
+/// Links are built from a URL template supporting the `{repo}`, `{rev}`, `{path}` and `{line}`
+/// placeholders, where `{path}` is relative to the repository root.
+pub struct SourceLinks {
+    /// URL template for source links
+    template: String,
+    /// Base URL of the repository
+    repo_url: String,
+    /// Revision that links should point at
+    revision: String,
+    /// Directory that link paths are relative to
+    root: PathBuf,
+}
+
+impl SourceLinks {
+    /// Creates a new SourceLinks generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - URL template for source links
+    /// * `repo_url` - Base URL of the repository, substituted for `{repo}`
+    /// * `revision` - Revision that links should point at, substituted for `{rev}`
+    /// * `root` - Directory that source paths are made relative to, substituted for `{path}`
+    pub fn new(template: String, repo_url: String, revision: String, root: PathBuf) -> Self {
+        SourceLinks {
+            template,
+            repo_url: repo_url.trim_end_matches('/').to_string(),
+            revision,
+            root,
+        }
+    }
+
+    /// Builds the URL pointing at a specific line of a source file.
+    pub fn url(&self, source_path: &Path, line: usize) -> String {
+        let relative_path = std::fs::canonicalize(source_path)
+            .ok()
+            .and_then(|p| p.strip_prefix(&self.root).ok().map(Path::to_path_buf))
+            .unwrap_or_else(|| source_path.to_path_buf());
+        let path = relative_path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        self.template
+            .replace("{repo}", &self.repo_url)
+            .replace("{rev}", &self.revision)
+            .replace("{path}", &path)
+            .replace("{line}", &line.to_string())
+    }
+
+    /// Inserts a "Source" link below the heading of every documented function.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Generated markdown for the source file
+    /// * `source_path` - Path to the documented source file
+    /// * `category` - Fully-qualified category name used as the prefix of function names
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source file cannot be read.
+    pub fn inject(&self, content: &str, source_path: &Path, category: &str) -> Result<String> {
+        let source = std::fs::read_to_string(source_path)
+            .with_context(|| format!("Failed to read source file: {}", source_path.display()))?;
+        let source = NixSource::parse(&source);
+
+        let links: HashMap<usize, String> = DocPage::parse(content)
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let name = entry
+                    .name
+                    .strip_prefix(category)
+                    .and_then(|n| n.strip_prefix('.'))
+                    .unwrap_or(&entry.name);
+                match source.attribute(name) {
+                    Some(attribute) => Some((entry.line, self.url(source_path, attribute.line))),
+                    None => {
+                        debug!("No source location found for {}", entry.name);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        let mut output = String::with_capacity(content.len());
+        for (index, line) in content.lines().enumerate() {
+            output.push_str(line);
+            output.push('\n');
+            if let Some(url) = links.get(&(index + 1)) {
+                output.push_str(&format!("\n[Source]({})\n", url));
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const PAGE: &str = r#"# Utility functions {#sec-functions-library-strings}
+
+## `lib.strings.simpleOption` {#function-library-lib.strings.simpleOption}
+
+A simple option
+
+## `lib.strings.missing` {#function-library-lib.strings.missing}
+
+Not present in the source
+"#;
+
+    #[test]
+    fn test_url_template() {
+        let links = SourceLinks::new(
+            DEFAULT_URL_TEMPLATE.to_string(),
+            "https://example.com/repo/".to_string(),
+            "abc123".to_string(),
+            PathBuf::from("/nonexistent"),
+        );
+
+        assert_eq!(
+            links.url(Path::new("lib/strings.nix"), 42),
+            "https://example.com/repo/blob/abc123/lib/strings.nix#L42"
+        );
+    }
+
+    #[test]
+    fn test_inject_links() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(temp_dir.path()).unwrap();
+        let source_path = root.join("lib").join("strings.nix");
+        fs::create_dir_all(source_path.parent().unwrap()).unwrap();
+        fs::write(&source_path, include_str!("../resources/test-lib.nix")).unwrap();
+
+        let links = SourceLinks::new(
+            "{repo}/src/{rev}/{path}?line={line}".to_string(),
+            "https://example.com/repo".to_string(),
+            "main".to_string(),
+            root,
+        );
+        let output = links.inject(PAGE, &source_path, "lib.strings").unwrap();
+
+        assert!(output.contains(
+            "## `lib.strings.simpleOption` {#function-library-lib.strings.simpleOption}\n\n\
+             [Source](https://example.com/repo/src/main/lib/strings.nix?line=10)\n\n\
+             A simple option\n"
+        ));
+        assert_eq!(output.matches("[Source]").count(), 1);
+    }
+}
@@ -0,0 +1,384 @@
+//! Selection of the paths walked within an input directory.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use ignore::{
+    Match, Walk, WalkBuilder,
+    gitignore::{Gitignore, GitignoreBuilder, Glob},
+};
+use log::warn;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::fragment::Fragments;
+
+/// Options controlling how an input directory is walked, configured in the `[walk]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WalkConfig {
+    /// Whether `.gitignore`, `.ignore` and git exclude files are respected [default: true]
+    #[schemars(extend("default" = true))]
+    pub gitignore: Option<bool>,
+    /// Names of additional gitignore-style files to respect, such as `.autonixdocignore`
+    #[serde(default)]
+    pub ignore_files: Vec<String>,
+    /// Whether symbolic links are followed [default: false]
+    #[schemars(extend("default" = false))]
+    pub follow_symlinks: Option<bool>,
+    /// Maximum depth of walked paths below the input directory
+    pub max_depth: Option<usize>,
+    /// Whether hidden files and directories are walked [default: false]
+    #[schemars(extend("default" = false))]
+    pub hidden: Option<bool>,
+}
+
+/// Gitignore-style include and exclude patterns, relative to an input directory.
+///
+/// Excluded directories are pruned from the walk entirely. When any include patterns are
+/// given, only files matching one of them are selected.
+#[derive(Debug)]
+pub struct PathFilter {
+    root: PathBuf,
+    options: WalkConfig,
+    include_patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    include: Gitignore,
+    exclude: Gitignore,
+    /// Configuration fragments whose exclude patterns also apply
+    fragments: Option<Arc<Fragments>>,
+    /// Patterns that matched at least one walked path
+    matched: Mutex<BTreeSet<String>>,
+}
+
+impl PathFilter {
+    /// Creates a filter for paths within `root`.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The input directory that patterns are relative to
+    /// * `include` - Patterns selecting the files to process
+    /// * `exclude` - Patterns of files and directories to leave out
+    /// * `options` - How the input directory is walked
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any pattern is not a valid glob.
+    pub fn new(
+        root: &Path,
+        include: &[String],
+        exclude: &[String],
+        options: WalkConfig,
+    ) -> Result<Self> {
+        let build = |patterns: &[String], name: &str| -> Result<Gitignore> {
+            let mut builder = GitignoreBuilder::new(root);
+            for pattern in patterns {
+                builder
+                    .add_line(None, pattern)
+                    .with_context(|| format!("Invalid {} pattern `{}`", name, pattern))?;
+            }
+            builder
+                .build()
+                .with_context(|| format!("Invalid {} patterns", name))
+        };
+
+        Ok(PathFilter {
+            root: root.to_path_buf(),
+            options,
+            include: build(include, "include")?,
+            exclude: build(exclude, "exclude")?,
+            include_patterns: include.to_vec(),
+            exclude_patterns: exclude.to_vec(),
+            fragments: None,
+            matched: Mutex::new(BTreeSet::new()),
+        })
+    }
+
+    /// Additionally excludes the paths excluded by configuration fragments within the root.
+    pub fn with_fragments(mut self, fragments: Arc<Fragments>) -> Self {
+        self.fragments = Some(fragments);
+        self
+    }
+
+    /// Walks the input directory, pruning excluded paths.
+    pub fn walk(self: &Arc<Self>) -> Walk {
+        let mut builder = WalkBuilder::new(&self.root);
+        builder
+            .standard_filters(self.options.gitignore.unwrap_or(true))
+            .hidden(!self.options.hidden.unwrap_or(false))
+            .follow_links(self.options.follow_symlinks.unwrap_or(false))
+            .max_depth(self.options.max_depth);
+        for name in &self.options.ignore_files {
+            builder.add_custom_ignore_filename(name);
+        }
+
+        let filter = Arc::clone(self);
+        builder
+            .filter_entry(move |entry| {
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                !filter.is_excluded(entry.path(), is_dir)
+            })
+            .build()
+    }
+
+    /// Returns whether a walked file is selected by the include patterns.
+    pub fn is_included(&self, path: &Path) -> bool {
+        if self.include.is_empty() {
+            return true;
+        }
+        let matched = self.include.matched_path_or_any_parents(path, false);
+        self.record(matched)
+    }
+
+    fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        // The root itself is never excluded
+        if path == self.root {
+            return false;
+        }
+        if !self.exclude.is_empty() && self.record(self.exclude.matched(path, is_dir)) {
+            return true;
+        }
+        self.fragments
+            .as_ref()
+            .is_some_and(|fragments| fragments.is_excluded(path, is_dir))
+    }
+
+    /// Records the pattern that decided a match, returning whether the path matched.
+    fn record(&self, matched: Match<&Glob>) -> bool {
+        let is_match = matched.is_ignore();
+        if let Match::Ignore(glob) | Match::Whitelist(glob) = matched {
+            self.matched
+                .lock()
+                .expect("path filter lock poisoned")
+                .insert(glob.original().to_string());
+        }
+        is_match
+    }
+
+    /// Returns the include and exclude patterns that didn't match any walked path.
+    pub fn unmatched(&self) -> Vec<&str> {
+        let matched = self.matched.lock().expect("path filter lock poisoned");
+        self.include_patterns
+            .iter()
+            .chain(&self.exclude_patterns)
+            .filter(|pattern| !matched.contains(pattern.as_str()))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Patterns that didn't match any walked path in any input directory of a run.
+///
+/// Patterns apply to every input directory, so they are only reported once none has a path
+/// they match.
+#[derive(Debug, Default)]
+pub struct UnmatchedPatterns {
+    /// Patterns unmatched so far, or `None` before any input directory was walked
+    unmatched: Option<BTreeSet<String>>,
+}
+
+impl UnmatchedPatterns {
+    /// Forgets every pattern that matched a path walked by `filter`.
+    pub fn retain(&mut self, filter: &PathFilter) {
+        let unmatched: BTreeSet<String> =
+            filter.unmatched().into_iter().map(str::to_string).collect();
+        self.unmatched = Some(match self.unmatched.take() {
+            Some(previous) => previous.intersection(&unmatched).cloned().collect(),
+            None => unmatched,
+        });
+    }
+
+    /// Warns about every pattern that didn't match any walked path.
+    pub fn warn(&self) {
+        for pattern in self.unmatched.iter().flatten() {
+            warn!(
+                "Pattern `{}` didn't match any path in any input directory",
+                pattern
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn walked(filter: &Arc<PathFilter>) -> Vec<String> {
+        let mut paths: Vec<String> = filter
+            .walk()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .filter(|entry| filter.is_included(entry.path()))
+            .map(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(&filter.root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn tree() -> tempfile::TempDir {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for path in [
+            "default.nix",
+            "strings/default.nix",
+            "strings/ascii.nix",
+            "tests/strings.nix",
+            "tests/fixtures/broken.nix",
+        ] {
+            let path = temp_dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{ }").unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_no_patterns() {
+        let temp_dir = tree();
+        let filter =
+            Arc::new(PathFilter::new(temp_dir.path(), &[], &[], WalkConfig::default()).unwrap());
+
+        assert_eq!(walked(&filter).len(), 5);
+        assert!(filter.unmatched().is_empty());
+    }
+
+    #[test]
+    fn test_exclude_prunes_directories() {
+        let temp_dir = tree();
+        let exclude = vec!["tests/".to_string(), "ascii.nix".to_string()];
+        let filter = Arc::new(
+            PathFilter::new(temp_dir.path(), &[], &exclude, WalkConfig::default()).unwrap(),
+        );
+
+        assert_eq!(walked(&filter), vec!["default.nix", "strings/default.nix"]);
+        assert!(filter.unmatched().is_empty());
+    }
+
+    #[test]
+    fn test_include() {
+        let temp_dir = tree();
+        let include = vec!["strings/".to_string(), "/default.nix".to_string()];
+        let exclude = vec!["ascii.nix".to_string()];
+        let filter = Arc::new(
+            PathFilter::new(temp_dir.path(), &include, &exclude, WalkConfig::default()).unwrap(),
+        );
+
+        assert_eq!(walked(&filter), vec!["default.nix", "strings/default.nix"]);
+    }
+
+    #[test]
+    fn test_unmatched() {
+        let temp_dir = tree();
+        let include = vec!["**/*.nix".to_string()];
+        let exclude = vec!["lists/".to_string()];
+        let filter = Arc::new(
+            PathFilter::new(temp_dir.path(), &include, &exclude, WalkConfig::default()).unwrap(),
+        );
+        walked(&filter);
+
+        assert_eq!(filter.unmatched(), vec!["lists/"]);
+    }
+
+    #[test]
+    fn test_unmatched_across_input_directories() {
+        let temp_dir = tree();
+        let patterns = vec!["lists/".to_string(), "strings/".to_string()];
+        let filter = |root: &Path| {
+            let filter =
+                Arc::new(PathFilter::new(root, &[], &patterns, WalkConfig::default()).unwrap());
+            walked(&filter);
+            filter
+        };
+        let mut unmatched = UnmatchedPatterns::default();
+
+        unmatched.retain(&filter(temp_dir.path()));
+        assert_eq!(
+            unmatched.unmatched,
+            Some(BTreeSet::from(["lists/".to_string()]))
+        );
+
+        fs::create_dir_all(temp_dir.path().join("other/lists")).unwrap();
+        fs::write(temp_dir.path().join("other/lists/default.nix"), "{ }").unwrap();
+        unmatched.retain(&filter(&temp_dir.path().join("other")));
+        assert_eq!(unmatched.unmatched, Some(BTreeSet::new()));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let temp_dir = tree();
+        let result = PathFilter::new(
+            temp_dir.path(),
+            &["{a,b".to_string()],
+            &[],
+            WalkConfig::default(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_walk_options() {
+        let temp_dir = tree();
+        fs::create_dir_all(temp_dir.path().join(".hidden")).unwrap();
+        fs::write(temp_dir.path().join(".hidden/secret.nix"), "{ }").unwrap();
+        fs::write(temp_dir.path().join(".autonixdocignore"), "tests/\n").unwrap();
+        std::os::unix::fs::symlink(
+            temp_dir.path().join("strings"),
+            temp_dir.path().join("linked"),
+        )
+        .unwrap();
+
+        let walk = |options: WalkConfig| {
+            let filter = Arc::new(PathFilter::new(temp_dir.path(), &[], &[], options).unwrap());
+            walked(&filter)
+        };
+
+        assert_eq!(
+            walk(WalkConfig::default()),
+            vec![
+                "default.nix",
+                "strings/ascii.nix",
+                "strings/default.nix",
+                "tests/fixtures/broken.nix",
+                "tests/strings.nix",
+            ]
+        );
+        assert_eq!(
+            walk(WalkConfig {
+                ignore_files: vec![".autonixdocignore".to_string()],
+                max_depth: Some(1),
+                ..Default::default()
+            }),
+            vec!["default.nix"]
+        );
+        assert_eq!(
+            walk(WalkConfig {
+                ignore_files: vec![".autonixdocignore".to_string()],
+                hidden: Some(true),
+                follow_symlinks: Some(true),
+                ..Default::default()
+            }),
+            vec![
+                ".autonixdocignore",
+                ".hidden/secret.nix",
+                "default.nix",
+                "linked/ascii.nix",
+                "linked/default.nix",
+                "strings/ascii.nix",
+                "strings/default.nix",
+            ]
+        );
+    }
+}
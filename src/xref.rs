@@ -0,0 +1,270 @@
+//! Cross-reference linking between documented functions.
+//!
+//! Doc comments frequently mention other functions in code spans (e.g. `` `lib.strings.concatMapStrings` ``).
+//! Once all documentation has been generated, every such span naming a documented function is
+//! rewritten into a relative markdown link to that function's anchor.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::markdown::{DocPage, is_code_fence, parse_heading};
+
+/// A reference to something that looks like a documented identifier but isn't one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedReference {
+    /// Page containing the reference
+    pub page: PathBuf,
+    /// 1-based line of the reference within the page
+    pub line: usize,
+    /// The referenced name
+    pub name: String,
+}
+
+/// Location of a documented identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Symbol {
+    page: PathBuf,
+    anchor: String,
+}
+
+/// Table of every documented identifier across all generated pages.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    symbols: HashMap<String, Symbol>,
+    /// First components of all symbols (e.g. `lib`), used to spot unresolved references
+    roots: HashSet<String>,
+}
+
+impl SymbolTable {
+    /// Registers every anchored function documented on a page.
+    pub fn add_page(&mut self, path: &Path, page: &DocPage) {
+        for entry in &page.entries {
+            let Some(anchor) = &entry.anchor else {
+                continue;
+            };
+
+            if let Some(root) = entry.name.split('.').next() {
+                self.roots.insert(root.to_string());
+            }
+            self.symbols.insert(
+                entry.name.clone(),
+                Symbol {
+                    page: path.to_path_buf(),
+                    anchor: anchor.clone(),
+                },
+            );
+        }
+    }
+
+    /// Rewrites recognized references within a page into links.
+    ///
+    /// Headings and code blocks are left untouched, as are code spans that are already part of
+    /// a link.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the page being rewritten
+    /// * `content` - Markdown content of the page
+    /// * `unresolved` - Receives references that look like identifiers but aren't documented
+    pub fn link(
+        &self,
+        path: &Path,
+        content: &str,
+        unresolved: &mut Vec<UnresolvedReference>,
+    ) -> String {
+        let mut output = String::with_capacity(content.len());
+        let mut in_fence = false;
+
+        for (index, line) in content.lines().enumerate() {
+            if is_code_fence(line) {
+                in_fence = !in_fence;
+                output.push_str(line);
+            } else if in_fence || parse_heading(line).is_some() {
+                output.push_str(line);
+            } else {
+                output.push_str(&self.link_line(path, index + 1, line, unresolved));
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+
+    fn link_line(
+        &self,
+        path: &Path,
+        line_number: usize,
+        line: &str,
+        unresolved: &mut Vec<UnresolvedReference>,
+    ) -> String {
+        let mut output = String::with_capacity(line.len());
+        let mut rest = line;
+
+        while let Some(start) = rest.find('`') {
+            let Some(length) = rest[start + 1..].find('`') else {
+                break;
+            };
+            let end = start + 1 + length;
+            let name = &rest[start + 1..end];
+            let before = &rest[..start];
+            let after = &rest[end + 1..];
+            let already_linked = before.ends_with('[') && after.starts_with("](");
+
+            output.push_str(before);
+            match self.symbols.get(name) {
+                Some(symbol) if !already_linked => {
+                    output.push_str(&format!(
+                        "[`{}`]({}#{})",
+                        name,
+                        relative_link(path, &symbol.page),
+                        symbol.anchor
+                    ));
+                }
+                None if self.looks_like_reference(name) => {
+                    unresolved.push(UnresolvedReference {
+                        page: path.to_path_buf(),
+                        line: line_number,
+                        name: name.to_string(),
+                    });
+                    output.push_str(&rest[start..=end]);
+                }
+                _ => output.push_str(&rest[start..=end]),
+            }
+            rest = after;
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    /// Returns true for dotted identifiers rooted at the same name as a documented symbol.
+    fn looks_like_reference(&self, name: &str) -> bool {
+        let mut parts = name.split('.');
+        let root_matches = parts.next().is_some_and(|root| self.roots.contains(root));
+        let mut parts = parts.peekable();
+
+        root_matches
+            && parts.peek().is_some()
+            && parts.all(|part| {
+                part.chars()
+                    .next()
+                    .is_some_and(|c| c.is_alphabetic() || c == '_')
+                    && part
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '\'' | '-'))
+            })
+    }
+}
+
+/// Computes the relative link from one page to another.
+///
+/// Links to the same page are empty so that only the fragment is used.
+pub fn relative_link(from: &Path, to: &Path) -> String {
+    if from == to {
+        return String::new();
+    }
+
+    let from_dir: Vec<Component> = from
+        .parent()
+        .map(|p| p.components().collect())
+        .unwrap_or_default();
+    let to_components: Vec<Component> = to.components().collect();
+    let common = from_dir
+        .iter()
+        .zip(&to_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts: Vec<String> =
+        std::iter::repeat_n("..".to_string(), from_dir.len() - common).collect();
+    parts.extend(
+        to_components[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> SymbolTable {
+        let strings = DocPage::parse(
+            "# Strings\n\n## `lib.strings.concatStrings` {#function-library-lib.strings.concatStrings}\n",
+        );
+        let lists =
+            DocPage::parse("# Lists\n\n## `lib.lists.map` {#function-library-lib.lists.map}\n");
+
+        let mut table = SymbolTable::default();
+        table.add_page(Path::new("text/strings.md"), &strings);
+        table.add_page(Path::new("lists.md"), &lists);
+        table
+    }
+
+    #[test]
+    fn test_relative_link() {
+        assert_eq!(
+            relative_link(Path::new("lists.md"), Path::new("text/strings.md")),
+            "text/strings.md"
+        );
+        assert_eq!(
+            relative_link(Path::new("text/strings.md"), Path::new("lists.md")),
+            "../lists.md"
+        );
+        assert_eq!(
+            relative_link(Path::new("a/b/c.md"), Path::new("a/d/e.md")),
+            "../d/e.md"
+        );
+        assert_eq!(relative_link(Path::new("a/b.md"), Path::new("a/b.md")), "");
+    }
+
+    #[test]
+    fn test_link_rewrites_references() {
+        let mut unresolved = Vec::new();
+        let output = table().link(
+            Path::new("lists.md"),
+            "See `lib.strings.concatStrings` and `lib.lists.map`.\n",
+            &mut unresolved,
+        );
+
+        assert_eq!(
+            output,
+            "See [`lib.strings.concatStrings`](text/strings.md#function-library-lib.strings.concatStrings) \
+             and [`lib.lists.map`](#function-library-lib.lists.map).\n"
+        );
+        assert!(unresolved.is_empty());
+    }
+
+    #[test]
+    fn test_link_skips_headings_code_blocks_and_links() {
+        let content = "## `lib.lists.map` {#function-library-lib.lists.map}\n\
+                       ```\n`lib.lists.map`\n```\n\
+                       [`lib.lists.map`](elsewhere.md)\n";
+        let mut unresolved = Vec::new();
+        let output = table().link(Path::new("lists.md"), content, &mut unresolved);
+
+        assert_eq!(output, content);
+    }
+
+    #[test]
+    fn test_link_reports_unresolved_references() {
+        let mut unresolved = Vec::new();
+        table().link(
+            Path::new("lists.md"),
+            "Unlike `lib.lists.missing`, `foo.bar` and `x` are not references.\n",
+            &mut unresolved,
+        );
+
+        assert_eq!(
+            unresolved,
+            vec![UnresolvedReference {
+                page: PathBuf::from("lists.md"),
+                line: 1,
+                name: "lib.lists.missing".to_string(),
+            }]
+        );
+    }
+}
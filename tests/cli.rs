@@ -161,9 +161,9 @@ fn test_failure_behavior_log() {
         .arg("--on-failure")
         .arg("log");
 
-    cmd.assert().success().stderr(
-        predicate::str::contains("Failed to generate documentation").or(predicate::str::is_empty()),
-    );
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("Failed to generate documentation"));
 
     let expected_output_file = output_dir.join("invalid.md");
     assert!(
@@ -435,7 +435,7 @@ fn test_cli_args_override_environment_variables() {
         .arg("--on-failure")
         .arg("log");
 
-    cmd.assert().success();
+    cmd.assert().code(2);
 }
 
 #[test]
@@ -949,3 +949,1566 @@ fn test_default_behavior_unchanged() {
         output_entries.len()
     );
 }
+
+#[test]
+fn test_man_pages_generated() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+    let man_dir = _temp_dir.path().join("man");
+
+    create_nix_file(
+        &input_dir,
+        "strings.nix",
+        include_str!("../resources/test-lib.nix"),
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--prefix")
+        .arg("lib")
+        .arg("--man-dir")
+        .arg(&man_dir)
+        .arg("--man-per-function");
+
+    cmd.assert().success();
+
+    let category_page = man_dir.join("lib.strings.3");
+    assert!(
+        category_page.exists(),
+        "Expected man page {:?} does not exist",
+        category_page
+    );
+
+    let content = fs::read_to_string(&category_page).expect("Failed to read man page");
+    assert!(content.contains(".SH NAME"));
+    assert!(content.contains(".SH DESCRIPTION"));
+
+    let function_page = man_dir.join("lib.strings.simpleOption.3");
+    assert!(
+        function_page.exists(),
+        "Expected man page {:?} does not exist",
+        function_page
+    );
+}
+
+#[test]
+fn test_search_index_generated() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+
+    let nested_dir = input_dir.join("text");
+    fs::create_dir_all(&nested_dir).expect("Failed to create nested directory");
+    create_nix_file(
+        &nested_dir,
+        "strings.nix",
+        include_str!("../resources/test-lib.nix"),
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--prefix")
+        .arg("lib")
+        .arg("--search-index");
+
+    cmd.assert().success();
+
+    let index_path = output_dir.join("search-index.json");
+    assert!(
+        index_path.exists(),
+        "Expected search index {:?} does not exist",
+        index_path
+    );
+
+    let content = fs::read_to_string(&index_path).expect("Failed to read search index");
+    assert!(content.contains("\"id\": \"lib.text.strings.simpleOption\""));
+    assert!(content.contains("\"path\": \"text/strings.md\""));
+    assert!(content.contains("\"summary\": \"A simple option\""));
+}
+
+#[test]
+fn test_source_links_injected() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+
+    create_nix_file(
+        &input_dir,
+        "strings.nix",
+        include_str!("../resources/test-lib.nix"),
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--prefix")
+        .arg("lib")
+        .arg("--repo-url")
+        .arg("https://example.com/repo")
+        .arg("--source-url-template")
+        .arg("{repo}/blob/{rev}/{path}#L{line}")
+        .arg("--source-rev")
+        .arg("abc123");
+
+    cmd.assert().success();
+
+    let content =
+        fs::read_to_string(output_dir.join("strings.md")).expect("Failed to read output file");
+    assert!(
+        content.contains("[Source](https://example.com/repo/blob/abc123/")
+            && content.contains("strings.nix#L10)"),
+        "Expected source link in generated documentation, found: {}",
+        content
+    );
+}
+
+#[test]
+fn test_source_links_per_source() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+
+    // Sources must not share a repository root for their links to differ
+    if temp_dir.path().ancestors().any(|d| d.join(".git").exists()) {
+        return;
+    }
+
+    let lib_dir = temp_dir.path().join("lib");
+    let modules_dir = temp_dir.path().join("modules");
+    let output_dir = temp_dir.path().join("output");
+    fs::create_dir_all(&lib_dir).unwrap();
+    fs::create_dir_all(&modules_dir).unwrap();
+    create_nix_file(
+        &lib_dir,
+        "strings.nix",
+        include_str!("../resources/test-lib.nix"),
+    );
+    create_nix_file(
+        &modules_dir,
+        "options.nix",
+        include_str!("../resources/test-lib.nix"),
+    );
+
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+    fs::write(
+        &config_file,
+        "ignore_paths = []\n\n[[sources]]\ninput_dir = \"lib\"\n\n[[sources]]\ninput_dir = \"modules\"\noutput_subdir = \"modules\"\n",
+    )
+    .expect("Failed to write config file");
+
+    let mut cmd = cli_command();
+    cmd.arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--repo-url")
+        .arg("https://example.com/repo")
+        .arg("--source-rev")
+        .arg("abc123");
+    cmd.assert().success();
+
+    let content =
+        fs::read_to_string(output_dir.join("strings.md")).expect("Failed to read output file");
+    assert!(content.contains("[Source](https://example.com/repo/blob/abc123/strings.nix#L10)"));
+    let content = fs::read_to_string(output_dir.join("modules/options.md"))
+        .expect("Failed to read output file");
+    assert!(content.contains("[Source](https://example.com/repo/blob/abc123/options.nix#L10)"));
+}
+
+#[test]
+fn test_source_links_require_revision() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+
+    // The revision can't be missing if the temporary directory happens to be within a repository
+    if _temp_dir
+        .path()
+        .ancestors()
+        .any(|d| d.join(".git").exists())
+    {
+        return;
+    }
+
+    create_nix_file(&input_dir, "test.nix", "{ lib }: { hello = \"world\"; }");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--repo-url")
+        .arg("https://example.com/repo");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("No source revision was provided"));
+}
+
+#[test]
+fn test_cross_references_linked() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+
+    let nested_dir = input_dir.join("text");
+    fs::create_dir_all(&nested_dir).expect("Failed to create nested directory");
+    create_nix_file(
+        &nested_dir,
+        "strings.nix",
+        "/**\n  Strings\n*/\n{ lib }:\n{\n  /**\n    Joins strings.\n  */\n  join = x: x;\n}\n",
+    );
+    create_nix_file(
+        &input_dir,
+        "lists.nix",
+        "/**\n  Lists\n*/\n{ lib }:\n{\n  /**\n    Like `lib.text.strings.join` but not `lib.text.strings.missing`.\n  */\n  concat = x: x;\n}\n",
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--prefix")
+        .arg("lib")
+        .arg("--logging-level")
+        .arg("warn")
+        .arg("--cross-references");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("lib.text.strings.missing"));
+
+    let content =
+        fs::read_to_string(output_dir.join("lists.md")).expect("Failed to read output file");
+    assert!(
+        content.contains("[`lib.text.strings.join`](text/strings.md#"),
+        "Expected cross-reference link in generated documentation, found: {}",
+        content
+    );
+}
+
+#[test]
+fn test_lint_reports_broken_links() {
+    let (_temp_dir, _input_dir, output_dir) = create_test_directory();
+    fs::create_dir_all(&output_dir).expect("Failed to create output directory");
+    fs::write(
+        output_dir.join("strings.md"),
+        "# Strings {#strings}\n\nSee [lists](lists.md#nope) and [gone](missing.md).\n",
+    )
+    .expect("Failed to write page");
+    fs::write(output_dir.join("lists.md"), "# Lists {#strings}\n").expect("Failed to write page");
+
+    let mut cmd = cli_command();
+    cmd.arg("lint").arg("--output-dir").arg(&output_dir);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("error[duplicate-anchor]"))
+        .stdout(predicate::str::contains("error[missing-anchor]"))
+        .stdout(predicate::str::contains("error[missing-file]"));
+}
+
+#[test]
+fn test_lint_generated_documentation() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+    create_nix_file(
+        &input_dir,
+        "lists.nix",
+        "/**\n  Lists\n*/\n{ lib }:\n{\n  /**\n    Concatenates lists.\n  */\n  concat = x: x;\n}\n",
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir);
+    cmd.assert().success();
+
+    let mut cmd = cli_command();
+    cmd.arg("lint")
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--format")
+        .arg("json");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[]"));
+}
+
+#[test]
+fn test_coverage_report() {
+    let (_temp_dir, input_dir, _output_dir) = create_test_directory();
+    let nested_dir = input_dir.join("text");
+    fs::create_dir_all(&nested_dir).expect("Failed to create nested directory");
+    create_nix_file(
+        &nested_dir,
+        "strings.nix",
+        "{ lib }:\n{\n  /**\n    Joins strings.\n  */\n  join = x: x;\n\n  split = x: x;\n}\n",
+    );
+    create_nix_file(
+        &input_dir,
+        "lists.nix",
+        "{ lib }:\n{\n  /**\n    Concatenates lists.\n  */\n  concat = x: x;\n}\n",
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("coverage").arg("--input-dir").arg(&input_dir);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "text/strings.nix: 1/2 documented (50.0%)",
+        ))
+        .stdout(predicate::str::contains("undocumented: split"))
+        .stdout(predicate::str::contains("Total: 2/3 documented (66.7%)"));
+
+    let mut cmd = cli_command();
+    cmd.arg("coverage")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--format")
+        .arg("json");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value =
+        serde_json::from_slice(&output).expect("Coverage report should be valid JSON");
+    assert_eq!(report["total"]["documented"], 2);
+    assert_eq!(report["total"]["total"], 3);
+}
+
+#[test]
+fn test_coverage_requirements_enforced() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+    let nested_dir = input_dir.join("text");
+    fs::create_dir_all(&nested_dir).expect("Failed to create nested directory");
+    create_nix_file(
+        &nested_dir,
+        "strings.nix",
+        "/**\n  Strings\n*/\n{ lib }:\n{\n  /**\n    Joins strings.\n  */\n  join = x: x;\n\n  split = x: x;\n}\n",
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("coverage")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--min-coverage")
+        .arg("40");
+    cmd.assert().success();
+
+    let mut cmd = cli_command();
+    cmd.arg("coverage")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--min-coverage-dir")
+        .arg("text/**=75")
+        .arg("--forbid-undocumented")
+        .arg("text/strings.nix");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("error[min-coverage-dir]"))
+        .stderr(predicate::str::contains(
+            "strings.nix:11: error[forbid-undocumented]: `split` is undocumented",
+        ));
+
+    let config_file = input_dir.join("autonixdoc.toml");
+    fs::write(
+        &config_file,
+        "ignore_paths = []\n\n[coverage]\nminimum = 90\n",
+    )
+    .expect("Failed to write config file");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_file);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "total coverage of 50.0% is below the required 90.0%",
+    ));
+}
+
+#[test]
+fn test_invalid_thresholds_rejected() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+    create_nix_file(&input_dir, "lists.nix", "{ lib }: { }");
+
+    let mut cmd = cli_command();
+    cmd.arg("coverage")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--min-coverage")
+        .arg("150");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Coverage requirement `minimum` of 150 is not a percentage between 0 and 100",
+    ));
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--forbid-undocumented")
+        .arg("lib/[");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid coverage glob: lib/["));
+    assert!(!output_dir.join("lists.md").exists());
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--max-failure-ratio")
+        .arg("2");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Maximum failure ratio 2 is not between 0 and 1",
+    ));
+}
+
+#[test]
+fn test_lint_reports_doc_comment_mistakes() {
+    let (_temp_dir, input_dir, _output_dir) = create_test_directory();
+    create_nix_file(
+        &input_dir,
+        "strings.nix",
+        "{ lib }:\n{\n  # Joins strings.\n  join = x: x;\n\n  /**\n    Splits strings.\n\n    ## Type\n  */\n  split = x: x;\n}\n",
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("lint").arg("--input-dir").arg(&input_dir);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "strings.nix:3: warning[line-comment-doc]",
+        ))
+        .stdout(predicate::str::contains(
+            "strings.nix:9: error[section-format]",
+        ));
+
+    let mut cmd = cli_command();
+    cmd.arg("lint")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--severity")
+        .arg("section-format=warning")
+        .arg("--format")
+        .arg("json");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let diagnostics: serde_json::Value =
+        serde_json::from_slice(&output).expect("Lint output should be valid JSON");
+    assert_eq!(diagnostics.as_array().map(Vec::len), Some(2));
+    assert_eq!(diagnostics[1]["rule"], "section-format");
+    assert_eq!(diagnostics[1]["severity"], "warning");
+}
+
+#[test]
+fn test_github_annotations_for_lint_findings() {
+    let (_temp_dir, input_dir, _output_dir) = create_test_directory();
+    create_nix_file(
+        &input_dir,
+        "strings.nix",
+        "{ lib }:\n{\n  /** */\n  join = x: x;\n}\n",
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("lint")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--github-annotations")
+        .arg("--format")
+        .arg("json");
+
+    let output = cmd
+        .assert()
+        .failure()
+        .stderr(
+            predicate::str::is_match(r"(?m)^::error file=.*strings\.nix,line=3,title=empty-doc::")
+                .unwrap(),
+        )
+        .get_output()
+        .stdout
+        .clone();
+    serde_json::from_slice::<serde_json::Value>(&output)
+        .expect("Lint output should remain valid JSON");
+}
+
+#[test]
+fn test_sarif_for_coverage_violations() {
+    let (temp_dir, input_dir, _output_dir) = create_test_directory();
+    create_nix_file(
+        &input_dir,
+        "strings.nix",
+        "{ lib }:\n{\n  join = x: x;\n}\n",
+    );
+    let sarif_file = temp_dir.path().join("results.sarif");
+
+    let mut cmd = cli_command();
+    cmd.arg("coverage")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--forbid-undocumented")
+        .arg("*.nix")
+        .arg("--sarif")
+        .arg(&sarif_file);
+
+    cmd.assert().failure();
+
+    let sarif: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&sarif_file).expect("Failed to read SARIF file"))
+            .expect("SARIF file should be valid JSON");
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(sarif["version"], "2.1.0");
+    assert_eq!(result["ruleId"], "forbid-undocumented");
+    assert_eq!(
+        result["locations"][0]["physicalLocation"]["region"]["startLine"],
+        3
+    );
+}
+
+#[test]
+fn test_run_summary_and_report() {
+    let (temp_dir, input_dir, output_dir) = create_test_directory();
+    create_nix_file(
+        &input_dir,
+        "lists.nix",
+        "/**\n  Lists\n*/\n{ lib }:\n{\n  /**\n    Concatenates lists.\n  */\n  concat = x: x;\n}\n",
+    );
+    create_nix_file(&input_dir, "ignored.nix", "{ }");
+    create_nix_file(&input_dir, "readme.txt", "Not a Nix file");
+
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+    fs::write(
+        &config_file,
+        format!(
+            "ignore_paths = [\"{}\"]\n",
+            input_dir.join("ignored.nix").display()
+        ),
+    )
+    .expect("Failed to write config file");
+    let report_file = temp_dir.path().join("report.json");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--report")
+        .arg(&report_file);
+
+    cmd.assert().success().stderr(predicate::str::contains(
+        "1 written, 1 skipped by mapping, 0 failed (1 skipped by identification)",
+    ));
+
+    let report: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(&report_file).expect("Failed to read report file"),
+    )
+    .expect("Report should be valid JSON");
+    assert_eq!(report["summary"]["processed"], 2);
+
+    let files = report["files"]
+        .as_array()
+        .expect("Report should list files");
+    let lists = files
+        .iter()
+        .find(|f| f["path"].as_str().is_some_and(|p| p.ends_with("lists.nix")))
+        .expect("Report should contain lists.nix");
+    assert_eq!(lists["status"], "written");
+    assert_eq!(lists["category"], "lists");
+    assert!(
+        lists["destination"]
+            .as_str()
+            .is_some_and(|d| d.ends_with("lists.md"))
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--quiet");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Processed").not());
+}
+
+#[test]
+fn test_failure_limits() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+    create_nix_file(&input_dir, "good.nix", "{ lib }: { hello = \"world\"; }");
+    create_nix_file(&input_dir, "bad1.nix", "this is not valid nix syntax {{{");
+    create_nix_file(&input_dir, "bad2.nix", "this is not valid nix syntax {{{");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--max-failures")
+        .arg("2");
+    cmd.assert().code(2);
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--max-failures")
+        .arg("1");
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("exceeding the maximum of 1"));
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--max-failure-ratio")
+        .arg("0.5");
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("2 of 3 files failed"));
+
+    // The run aborts on the first failure rather than once every file was processed
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--max-failure-ratio")
+        .arg("0");
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "Aborting after 1 of 3 files failed",
+    ));
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--on-failure")
+        .arg("skip");
+    cmd.assert().code(0);
+}
+
+#[test]
+fn test_failure_behavior_per_error_kind() {
+    let (temp_dir, input_dir, output_dir) = create_test_directory();
+    create_nix_file(&input_dir, "good.nix", "{ lib }: { hello = \"world\"; }");
+    create_nix_file(&input_dir, "bad.nix", "this is not valid nix syntax {{{");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--on-failure")
+        .arg("skip")
+        .arg("--on-failure-for")
+        .arg("nixdoc-failed=abort");
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("nixdoc failed for"));
+
+    let config_path = temp_dir.path().join("config.toml");
+    fs::write(
+        &config_path,
+        "ignore_paths = []\n\n[failure_behaviors]\nnixdoc-failed = \"Skip\"\n",
+    )
+    .unwrap();
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_path);
+    cmd.assert().code(0);
+
+    let mut cmd = cli_command();
+    cmd.env("PATH", "")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--on-failure-for")
+        .arg("nixdoc-missing=abort");
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("nixdoc could not be found"));
+}
+
+#[test]
+fn test_symlinked_and_unnormalized_input_dir() {
+    let (temp_dir, input_dir, output_dir) = create_test_directory();
+    fs::create_dir_all(input_dir.join("lib")).unwrap();
+    create_nix_file(
+        &input_dir.join("lib"),
+        "strings.nix",
+        "{ lib }: { hello = \"world\"; }",
+    );
+    let link = temp_dir.path().join("link");
+    std::os::unix::fs::symlink(&input_dir, &link).unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(link.join(".").join("lib").join(".."))
+        .arg("--output-dir")
+        .arg(&output_dir);
+    cmd.assert().success();
+
+    assert!(output_dir.join("lib/strings.md").exists());
+}
+
+#[test]
+fn test_junit_report() {
+    let (temp_dir, input_dir, output_dir) = create_test_directory();
+    fs::create_dir_all(input_dir.join("strings")).unwrap();
+    create_nix_file(&input_dir, "good.nix", "{ lib }: { hello = \"world\"; }");
+    create_nix_file(
+        &input_dir.join("strings"),
+        "bad.nix",
+        "this is not valid nix syntax {{{",
+    );
+    let junit_file = temp_dir.path().join("junit.xml");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--junit")
+        .arg(&junit_file);
+    cmd.assert().code(2);
+
+    let junit = fs::read_to_string(&junit_file).expect("Failed to read JUnit report");
+    assert!(junit.contains("<testsuites name=\"autonixdoc\" tests=\"2\" failures=\"1\""));
+    assert!(junit.contains("<testsuite name=\".\" tests=\"1\" failures=\"0\""));
+    assert!(junit.contains("<testsuite name=\"strings\" tests=\"1\" failures=\"1\""));
+    assert!(junit.contains("<testcase name=\"strings/bad.nix\" classname=\"strings\""));
+    assert!(junit.contains("<failure message=\"nixdoc failed for"));
+}
+
+#[test]
+fn test_multiple_sources() {
+    let (temp_dir, _input_dir, output_dir) = create_test_directory();
+    let lib_dir = temp_dir.path().join("lib");
+    let modules_dir = temp_dir.path().join("modules").join("lib");
+    fs::create_dir_all(&lib_dir).unwrap();
+    fs::create_dir_all(&modules_dir).unwrap();
+    let documented = "{ lib }:\n{\n  /**\n    Says hello.\n  */\n  hello = x: x;\n}\n";
+    create_nix_file(&lib_dir, "strings.nix", documented);
+    create_nix_file(&modules_dir, "options.nix", documented);
+    create_nix_file(
+        &modules_dir,
+        "broken.nix",
+        "this is not valid nix syntax {{{",
+    );
+
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+    fs::write(
+        &config_file,
+        format!(
+            r#"ignore_paths = []
+prefix = "lib"
+
+[[sources]]
+input_dir = "{}"
+
+[[sources]]
+input_dir = "{}"
+output_subdir = "modules"
+category_root = "modules"
+prefix = "modulesLib"
+"#,
+            lib_dir.display(),
+            modules_dir.display()
+        ),
+    )
+    .expect("Failed to write config file");
+    let report_file = temp_dir.path().join("report.json");
+    let junit_file = temp_dir.path().join("junit.xml");
+
+    let mut cmd = cli_command();
+    cmd.arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--report")
+        .arg(&report_file)
+        .arg("--junit")
+        .arg(&junit_file)
+        .arg("--search-index");
+    cmd.assert().code(2).stderr(predicate::str::contains(
+        "2 written, 0 skipped by mapping, 1 failed",
+    ));
+
+    assert!(output_dir.join("strings.md").exists());
+    assert!(output_dir.join("modules/options.md").exists());
+
+    let report = fs::read_to_string(&report_file).expect("Failed to read report file");
+    assert!(report.contains("\"category\": \"strings\""));
+    assert!(report.contains("\"category\": \"modules.options\""));
+
+    let junit = fs::read_to_string(&junit_file).expect("Failed to read JUnit report");
+    assert!(junit.contains("<testsuite name=\"lib\" tests=\"1\" failures=\"0\""));
+    assert!(junit.contains("<testcase name=\"modules/lib/broken.nix\" classname=\"modules/lib\""));
+
+    let index = fs::read_to_string(output_dir.join("search-index.json"))
+        .expect("Failed to read search index");
+    assert!(index.contains("\"id\": \"modulesLib.modules.options.hello\""));
+    assert!(index.contains("\"path\": \"modules/options.md\""));
+    assert!(index.contains("\"id\": \"lib.strings.hello\""));
+}
+
+#[test]
+fn test_input_dir_or_sources_required() {
+    let (_temp_dir, _input_dir, output_dir) = create_test_directory();
+
+    let mut cmd = cli_command();
+    cmd.arg("--output-dir").arg(&output_dir);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("configured in [[sources]]"));
+}
+
+#[test]
+fn test_coverage_and_lint_every_source() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let lib_dir = temp_dir.path().join("lib");
+    let modules_dir = temp_dir.path().join("modules");
+    fs::create_dir_all(&lib_dir).unwrap();
+    fs::create_dir_all(&modules_dir).unwrap();
+    create_nix_file(
+        &lib_dir,
+        "strings.nix",
+        "{ lib }:\n{\n  /**\n    Says hello.\n  */\n  hello = x: x;\n}\n",
+    );
+    create_nix_file(
+        &modules_dir,
+        "options.nix",
+        "{ lib }:\n{\n  # Says hello.\n  hello = x: x;\n}\n",
+    );
+
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+    fs::write(
+        &config_file,
+        "ignore_paths = []\n\n[[sources]]\ninput_dir = \"lib\"\n\n[[sources]]\ninput_dir = \"modules\"\noutput_subdir = \"modules\"\n",
+    )
+    .expect("Failed to write config file");
+
+    let mut cmd = cli_command();
+    cmd.arg("coverage").arg("--config").arg(&config_file);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "lib/strings.nix: 1/1 documented (100.0%)",
+        ))
+        .stdout(predicate::str::contains(
+            "modules/options.nix: 0/1 documented (0.0%)",
+        ))
+        .stdout(predicate::str::contains("Total: 1/2 documented (50.0%)"));
+
+    // The minimum applies to all sources together
+    let mut cmd = cli_command();
+    cmd.arg("coverage")
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--min-coverage")
+        .arg("50");
+    cmd.assert().success();
+
+    let mut cmd = cli_command();
+    cmd.arg("coverage")
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--min-coverage")
+        .arg("60");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "total coverage of 50.0% is below the required 60.0%",
+    ));
+
+    let mut cmd = cli_command();
+    cmd.arg("coverage")
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--forbid-undocumented")
+        .arg("*.nix");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "options.nix:4: error[forbid-undocumented]: `hello` is undocumented",
+        ))
+        .stderr(predicate::str::contains("(1 violation(s))"));
+
+    let mut cmd = cli_command();
+    cmd.arg("lint").arg("--config").arg(&config_file);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "options.nix:3: warning[line-comment-doc]",
+    ));
+}
+
+#[test]
+fn test_ineffective_settings_warned_once() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    for dir in ["lib/internal", "modules"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    let documented = "{ lib }:\n{\n  /**\n    Says hello.\n  */\n  hello = x: x;\n}\n";
+    create_nix_file(&root.join("lib"), "strings.nix", documented);
+    create_nix_file(&root.join("lib/internal"), "secrets.nix", documented);
+    create_nix_file(&root.join("modules"), "options.nix", documented);
+    fs::write(
+        root.join("autonixdoc.toml"),
+        r#"ignore_paths = ["strings.nix", "missing.nix"]
+output_dir = "docs"
+exclude = ["internal/", "attrsets/"]
+
+[coverage]
+minimum = 50
+
+[[sources]]
+input_dir = "lib"
+
+[[sources]]
+input_dir = "modules"
+output_subdir = "modules"
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = cli_command();
+    cmd.current_dir(root).arg("--logging-level").arg("warn");
+    let output = cmd.assert().success().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+
+    assert_eq!(stderr.matches("Pattern `attrsets/`").count(), 1);
+    assert_eq!(stderr.matches("Ignored path missing.nix").count(), 1);
+    assert!(!stderr.contains("internal/"));
+    assert!(!stderr.contains("Ignored path strings.nix"));
+}
+
+#[test]
+fn test_include_and_exclude_patterns() {
+    let (temp_dir, input_dir, output_dir) = create_test_directory();
+    for dir in ["strings", "tests", "lists"] {
+        fs::create_dir_all(input_dir.join(dir)).unwrap();
+    }
+    create_nix_file(&input_dir, "default.nix", "{ lib }: { hello = \"world\"; }");
+    create_nix_file(
+        &input_dir.join("strings"),
+        "ascii.nix",
+        "{ lib }: { hello = \"world\"; }",
+    );
+    create_nix_file(
+        &input_dir.join("tests"),
+        "broken.nix",
+        "this is not valid nix syntax {{{",
+    );
+    create_nix_file(
+        &input_dir.join("lists"),
+        "default.nix",
+        "{ lib }: { hello = \"world\"; }",
+    );
+
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+    fs::write(
+        &config_file,
+        "ignore_paths = []\ninclude = [\"strings/\", \"/default.nix\"]\nexclude = [\"tests/\", \"attrsets/\"]\n",
+    )
+    .expect("Failed to write config file");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--logging-level")
+        .arg("warn");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "2 written, 0 skipped by mapping, 0 failed (1 skipped by identification)",
+        ))
+        .stderr(predicate::str::contains(
+            "Pattern `attrsets/` didn't match any path",
+        ));
+
+    assert!(output_dir.join("default.md").exists());
+    assert!(output_dir.join("strings/ascii.md").exists());
+    assert!(!output_dir.join("lists/default.md").exists());
+    assert!(!output_dir.join("tests").exists());
+}
+
+#[test]
+fn test_ignore_paths_relative_to_input_directory() {
+    let (temp_dir, input_dir, output_dir) = create_test_directory();
+    fs::create_dir_all(input_dir.join("tests")).unwrap();
+    create_nix_file(&input_dir, "strings.nix", "{ lib }: { hello = \"world\"; }");
+    create_nix_file(&input_dir, "lists.nix", "{ lib }: { hello = \"world\"; }");
+    create_nix_file(
+        &input_dir.join("tests"),
+        "broken.nix",
+        "this is not valid nix syntax {{{",
+    );
+
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+    fs::write(
+        &config_file,
+        "ignore_paths = [\"input/strings.nix\", \"tests\", \"missing.nix\"]\n",
+    )
+    .expect("Failed to write config file");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(input_dir.join("."))
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--logging-level")
+        .arg("warn");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "1 written, 2 skipped by mapping, 0 failed",
+        ))
+        .stderr(predicate::str::contains(
+            "Ignored path missing.nix doesn't exist",
+        ));
+
+    assert!(output_dir.join("lists.md").exists());
+    assert!(!output_dir.join("strings.md").exists());
+}
+
+#[test]
+fn test_walk_options() {
+    let (temp_dir, input_dir, output_dir) = create_test_directory();
+    fs::create_dir_all(input_dir.join(".internal")).unwrap();
+    fs::create_dir_all(input_dir.join("nested")).unwrap();
+    let valid = "{ lib }: { hello = \"world\"; }";
+    create_nix_file(&input_dir, "default.nix", valid);
+    create_nix_file(&input_dir, "generated.nix", valid);
+    create_nix_file(&input_dir.join(".internal"), "secret.nix", valid);
+    create_nix_file(&input_dir.join("nested"), "deep.nix", valid);
+    fs::write(input_dir.join(".ignore"), "generated.nix\n").unwrap();
+    fs::write(input_dir.join(".autonixdocignore"), "default.nix\n").unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Processed 2 file(s)"));
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--no-gitignore")
+        .arg("--hidden");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Processed 4 file(s)"));
+    assert!(output_dir.join(".internal/secret.md").exists());
+
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+    fs::write(
+        &config_file,
+        "ignore_paths = []\n\n[walk]\nignore_files = [\".autonixdocignore\"]\nmax_depth = 1\n",
+    )
+    .expect("Failed to write config file");
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_file);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Processed 0 file(s)"));
+}
+
+#[test]
+fn test_directory_config_fragments() {
+    let (_temp_dir, input_dir, output_dir) = create_test_directory();
+    let strings_dir = input_dir.join("strings");
+    fs::create_dir_all(strings_dir.join("tests")).unwrap();
+    let documented =
+        "# Header\n# Second line\n{ lib }: {\n  /** Greets */\n  hello = \"world\";\n}";
+    create_nix_file(&input_dir, "default.nix", documented);
+    create_nix_file(&strings_dir, "ascii.nix", documented);
+    create_nix_file(&strings_dir, "broken.nix", "{{{ invalid");
+    create_nix_file(&strings_dir.join("tests"), "ascii.nix", documented);
+    fs::write(
+        strings_dir.join("autonixdoc.toml"),
+        "prefix = \"text\"\nfailure_behavior = \"Skip\"\nexclude = [\"tests/\"]\n",
+    )
+    .unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--prefix")
+        .arg("lib");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Processed 3 file(s)"));
+
+    let root = fs::read_to_string(output_dir.join("default.md")).unwrap();
+    assert!(root.contains("# # Second line"));
+    assert!(root.contains("`lib.default.hello`"));
+    let ascii = fs::read_to_string(output_dir.join("strings/ascii.md")).unwrap();
+    assert!(ascii.starts_with("# # Second line"));
+    assert!(ascii.contains("`text.strings.ascii.hello`"));
+    assert!(!output_dir.join("strings/tests").exists());
+}
+
+#[test]
+fn test_malformed_config_fragment() {
+    let (temp_dir, input_dir, output_dir) = create_test_directory();
+    let strings_dir = input_dir.join("strings");
+    fs::create_dir_all(&strings_dir).unwrap();
+    let documented = "{ lib }: {\n  /** Greets */\n  hello = \"world\";\n}";
+    create_nix_file(&input_dir, "default.nix", documented);
+    create_nix_file(&strings_dir, "ascii.nix", documented);
+    fs::write(strings_dir.join("autonixdoc.toml"), "prefx = \"text\"\n").unwrap();
+    let report_file = temp_dir.path().join("report.json");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--on-failure")
+        .arg("log")
+        .arg("--report")
+        .arg(&report_file);
+    cmd.assert().code(2).stderr(predicate::str::contains(
+        "1 written, 0 skipped by mapping, 1 failed",
+    ));
+    assert!(output_dir.join("default.md").exists());
+    let report = fs::read_to_string(&report_file).unwrap();
+    assert!(report.contains("\"error_kind\": \"invalid-fragment\""));
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--on-failure-for")
+        .arg("invalid-fragment=abort");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "invalid configuration fragment for",
+    ));
+}
+
+#[test]
+fn test_config_show() {
+    let (_temp_dir, input_dir, _output_dir) = create_test_directory();
+    let strings_dir = input_dir.join("strings");
+    fs::create_dir_all(&strings_dir).unwrap();
+    create_nix_file(&strings_dir, "ascii.nix", "{ lib }: { }");
+    fs::write(
+        strings_dir.join("autonixdoc.toml"),
+        "prefix = \"text\"\nfailure_behavior = \"Skip\"\nexclude = [\"tests/\"]\n",
+    )
+    .unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg("config")
+        .arg("show")
+        .arg(strings_dir.join("ascii.nix"))
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--anchor-prefix")
+        .arg("lib-")
+        .arg("--on-failure-for")
+        .arg("nixdoc-missing=abort");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("# Overridden by"))
+        .stdout(predicate::str::contains("prefix = \"text\""))
+        .stdout(predicate::str::contains("anchor_prefix = \"lib-\""))
+        .stdout(predicate::str::contains("failure_behavior = \"Skip\""))
+        .stdout(predicate::str::contains("nixdoc-missing = \"Abort\""))
+        .stdout(predicate::str::contains("strings = [\"tests/\"]"));
+
+    let mut cmd = cli_command();
+    cmd.arg("config")
+        .arg("show")
+        .arg("/elsewhere/default.nix")
+        .arg("--input-dir")
+        .arg(&input_dir);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is not within an input directory"));
+}
+
+#[test]
+fn test_unknown_config_key_rejected() {
+    let (temp_dir, input_dir, output_dir) = create_test_directory();
+    create_nix_file(&input_dir, "default.nix", "{ lib }: { }");
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+    fs::write(&config_file, "ignore_path = []\n").expect("Failed to write config file");
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--config")
+        .arg(&config_file);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "autonixdoc.toml:1:1: unknown field `ignore_path`",
+        ))
+        .stderr(predicate::str::contains("did you mean `ignore_paths`?"));
+}
+
+#[test]
+fn test_config_validate() {
+    let (temp_dir, input_dir, _output_dir) = create_test_directory();
+    let strings_dir = input_dir.join("strings");
+    fs::create_dir_all(&strings_dir).unwrap();
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+    fs::write(
+        &config_file,
+        "ignore_paths = []\nlogging_level = \"warn\"\n\n[walk]\nhidden = true\n",
+    )
+    .expect("Failed to write config file");
+
+    // Options of the driver are accepted after subcommands too
+    let mut cmd = cli_command();
+    cmd.arg("config")
+        .arg("validate")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--search-index")
+        .arg("--report")
+        .arg(temp_dir.path().join("report.json"))
+        .arg("--max-failure-ratio")
+        .arg("0.5");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Configuration is valid"));
+
+    fs::write(
+        &config_file,
+        "ignore_paths = []\nmax_failure_ratio = 2.0\n\n[lint.severity]\nempty-docs = \"error\"\n",
+    )
+    .expect("Failed to write config file");
+    fs::write(strings_dir.join("autonixdoc.toml"), "prefx = \"text\"\n").unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg("config")
+        .arg("validate")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--config")
+        .arg(&config_file);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Maximum failure ratio 2 is not between 0 and 1",
+        ))
+        .stderr(predicate::str::contains("Unknown lint rule `empty-docs`"))
+        .stderr(predicate::str::contains("did you mean `prefix`?"))
+        .stderr(predicate::str::contains(
+            "Configuration is invalid (3 problem(s))",
+        ));
+}
+
+#[test]
+fn test_config_schema() {
+    let mut cmd = cli_command();
+    cmd.arg("config").arg("schema");
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    let schema: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        schema["$schema"],
+        "https://json-schema.org/draft/2020-12/schema"
+    );
+    assert!(schema["properties"]["ignore_paths"].is_object());
+    assert!(schema["$defs"]["SourceConfig"].is_object());
+}
+
+#[test]
+fn test_init_writes_configuration() {
+    let (temp_dir, input_dir, _output_dir) = create_test_directory();
+    create_nix_file(&input_dir, "default.nix", "{ lib }: { }");
+    fs::create_dir_all(input_dir.join("tests")).unwrap();
+    let config_file = temp_dir.path().join("autonixdoc.toml");
+
+    let mut cmd = cli_command();
+    cmd.arg("init")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--config")
+        .arg(&config_file);
+    cmd.assert().success();
+
+    let config = fs::read_to_string(&config_file).unwrap();
+    assert!(config.starts_with("# Configuration for autonixdoc"));
+    assert!(config.contains("exclude = [\"/tests/\"]"));
+
+    let mut cmd = cli_command();
+    cmd.arg("config")
+        .arg("validate")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--config")
+        .arg(&config_file);
+    cmd.assert().success();
+
+    fs::write(&config_file, "# Hand-written\nignore_paths = []\n").unwrap();
+    let mut cmd = cli_command();
+    cmd.arg("init")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--config")
+        .arg(&config_file);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --force to overwrite it"));
+    assert!(
+        fs::read_to_string(&config_file)
+            .unwrap()
+            .starts_with("# Hand-written")
+    );
+
+    let mut cmd = cli_command();
+    cmd.arg("init")
+        .arg("--force")
+        .arg("--input-dir")
+        .arg(&input_dir)
+        .arg("--config")
+        .arg(&config_file);
+    cmd.assert().success();
+    assert!(
+        fs::read_to_string(&config_file)
+            .unwrap()
+            .starts_with("# Configuration for autonixdoc")
+    );
+}
+
+#[test]
+fn test_config_discovered_from_input_directory() {
+    let (temp_dir, _input_dir, output_dir) = create_test_directory();
+    let root = temp_dir.path();
+    let lib_dir = root.join("nix/lib");
+    fs::create_dir_all(&lib_dir).unwrap();
+    fs::create_dir_all(root.join("docs")).unwrap();
+    create_nix_file(root, "flake.nix", "{ }");
+    create_nix_file(
+        &lib_dir,
+        "strings.nix",
+        "{ lib }:\n{\n  /** Says hello. */\n  hello = x: x;\n}\n",
+    );
+    fs::write(
+        root.join("autonixdoc.toml"),
+        "ignore_paths = []\nprefix = \"discovered\"\n\n[[sources]]\ninput_dir = \"nix/lib\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg("--input-dir")
+        .arg(&lib_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg("--logging-level")
+        .arg("info");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "Using configuration file {}",
+            root.join("autonixdoc.toml").display()
+        )));
+    let strings = fs::read_to_string(output_dir.join("strings.md")).unwrap();
+    assert!(strings.contains("`discovered.strings.hello`"));
+
+    // Without an input directory, discovery starts from the current directory and configured
+    // sources are relative to the configuration file
+    fs::remove_dir_all(&output_dir).unwrap();
+    let mut cmd = cli_command();
+    cmd.current_dir(root.join("docs"))
+        .arg("--output-dir")
+        .arg(&output_dir);
+    cmd.assert().success();
+    assert!(output_dir.join("strings.md").exists());
+}
+
+#[test]
+fn test_bare_invocation_uses_configuration() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let lib_dir = root.join("lib");
+    fs::create_dir_all(&lib_dir).unwrap();
+    fs::create_dir_all(root.join("docs")).unwrap();
+    create_nix_file(root, "flake.nix", "{ }");
+    let documented = "{ lib }:\n{\n  /** Says hello. */\n  hello = x: x;\n}\n";
+    create_nix_file(&lib_dir, "strings.nix", documented);
+    create_nix_file(&lib_dir, "lists.nix.in", documented);
+    fs::write(
+        root.join("autonixdoc.toml"),
+        r#"ignore_paths = []
+input_dir = "lib"
+output_dir = "docs/lib"
+mapping = "auto"
+regex_pattern = "\\.nix(\\.in)?$"
+prefix = "lib"
+search_index = true
+report = "report.json"
+"#,
+    )
+    .unwrap();
+
+    // Paths in the configuration are relative to it, wherever autonixdoc runs within the repo
+    let mut cmd = cli_command();
+    cmd.current_dir(root.join("docs"));
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Processed 2 file(s)"));
+
+    assert!(root.join("docs/lib/strings.md").exists());
+    assert!(root.join("docs/lib/lists.md").exists());
+    let index = fs::read_to_string(root.join("docs/lib/search-index.json")).unwrap();
+    assert!(index.contains("\"id\": \"lib.strings.hello\""));
+    assert!(root.join("report.json").exists());
+
+    // Options on the command line still take precedence
+    let output_dir = root.join("elsewhere");
+    let mut cmd = cli_command();
+    cmd.current_dir(root).arg("--output-dir").arg(&output_dir);
+    cmd.assert().success();
+    assert!(output_dir.join("strings.md").exists());
+}
+
+#[test]
+fn test_init_then_bare_invocation() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let lib_dir = root.join("lib");
+    fs::create_dir_all(&lib_dir).unwrap();
+    create_nix_file(
+        &lib_dir,
+        "strings.nix",
+        "{ lib }:\n{\n  /** Says hello. */\n  hello = x: x;\n}\n",
+    );
+
+    let mut cmd = cli_command();
+    cmd.current_dir(root)
+        .arg("init")
+        .arg("--input-dir")
+        .arg("lib");
+    cmd.assert().success();
+    let config = fs::read_to_string(root.join("autonixdoc.toml")).unwrap();
+    assert!(config.contains("input_dir = \"lib\""));
+    assert!(config.contains("output_dir = \"docs/lib\""));
+
+    let mut cmd = cli_command();
+    cmd.current_dir(root);
+    cmd.assert().success();
+    assert!(root.join("docs/lib/strings.md").exists());
+}
+
+#[test]
+fn test_profiles() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let lib_dir = root.join("lib");
+    fs::create_dir_all(lib_dir.join("internal")).unwrap();
+    let documented = "{ lib }:\n{\n  /** Says hello. */\n  hello = x: x;\n}\n";
+    create_nix_file(&lib_dir, "strings.nix", documented);
+    create_nix_file(&lib_dir.join("internal"), "secrets.nix", documented);
+    fs::write(
+        root.join("autonixdoc.toml"),
+        r#"ignore_paths = []
+input_dir = "lib"
+output_dir = "docs/internal"
+prefix = "lib"
+
+[profile.internal]
+
+[profile.public]
+output_dir = "docs/public"
+prefix = "pub"
+exclude = ["internal/"]
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = cli_command();
+    cmd.current_dir(root)
+        .arg("--profile")
+        .arg("internal")
+        .arg("--profile")
+        .arg("public")
+        .arg("--report")
+        .arg("report.json");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("[internal] Processed 2 file(s)"))
+        .stderr(predicate::str::contains("[public] Processed 1 file(s)"));
+    assert!(root.join("report.internal.json").exists());
+    let report = fs::read_to_string(root.join("report.public.json")).unwrap();
+    assert!(report.contains("\"processed\": 1"));
+
+    assert!(root.join("docs/internal/internal/secrets.md").exists());
+    assert!(!root.join("docs/public/internal").exists());
+    let strings = fs::read_to_string(root.join("docs/public/strings.md")).unwrap();
+    assert!(strings.contains("`pub.strings.hello`"));
+
+    let config = fs::read_to_string(root.join("autonixdoc.toml")).unwrap();
+    fs::write(
+        root.join("autonixdoc.toml"),
+        config.replace(
+            "[profile.internal]\n",
+            "[profile.internal]\nlogging_level = \"warn\"\n",
+        ) + "logging_level = \"debug\"\n",
+    )
+    .unwrap();
+    let mut cmd = cli_command();
+    cmd.current_dir(root)
+        .env("AUTONIXDOC_PROFILE", "internal,public");
+    cmd.assert().success().stderr(predicate::str::contains(
+        "Ignoring the logging level of profile public",
+    ));
+
+    let mut cmd = cli_command();
+    cmd.current_dir(root).env("AUTONIXDOC_PROFILE", "publc");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("did you mean `public`?"));
+
+    fs::write(
+        root.join("autonixdoc.toml"),
+        "ignore_paths = []\n\n[profile.public]\nprefx = \"pub\"\n",
+    )
+    .unwrap();
+    let mut cmd = cli_command();
+    cmd.current_dir(root).arg("config").arg("validate");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "[profile.public]: unknown field `prefx`",
+    ));
+}